@@ -0,0 +1,80 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use metars::Metar;
+
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/metars.cache.csv"
+);
+
+// Roughly the size of the full CONUS feed.
+const COPIES: usize = 1000;
+
+fn write_feed(path: &Path) -> usize {
+    let fixture = fs::read_to_string(FIXTURE).unwrap();
+
+    let header = fixture.find("raw_text,").unwrap();
+    let (preamble, data) = fixture.split_at(header);
+    let (columns, rows) = data.split_once('\n').unwrap();
+
+    let mut feed = format!("{preamble}{columns}\n");
+
+    for _ in 0..COPIES {
+        feed.push_str(rows);
+    }
+
+    fs::write(path, feed).unwrap();
+
+    rows.lines().count() * COPIES
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("feed.csv");
+    let path = dir.path().join("metars.csv");
+
+    let rows = write_feed(&source);
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&fs::read(&source).unwrap()).unwrap();
+    let archive = encoder.finish().unwrap();
+
+    let mut group = c.benchmark_group("metars");
+    group.throughput(Throughput::Elements(rows as u64));
+
+    // The fetch stage needs the network, so only the offline stages are measured here.
+    group.bench_function("decompress", |b| {
+        b.iter(|| {
+            let mut contents = String::new();
+            GzDecoder::new(archive.as_slice())
+                .read_to_string(&mut contents)
+                .unwrap();
+            contents
+        })
+    });
+
+    group.bench_function("read_metar_file", |b| {
+        b.iter(|| {
+            fs::copy(&source, &path).unwrap();
+            Metar::read_metar_file(path.to_str().unwrap()).unwrap()
+        })
+    });
+
+    fs::copy(&source, &path).unwrap();
+    let dataframe = Metar::read_metar_file(path.to_str().unwrap()).unwrap();
+
+    group.bench_function("parse_metars", |b| {
+        b.iter(|| Metar::parse_metars(&dataframe))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
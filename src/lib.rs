@@ -0,0 +1,4130 @@
+// #![warn(clippy::all)]
+// #![warn(clippy::nursery)]
+// #![warn(clippy::pedantic)]
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::future::Future;
+use std::io::{BufReader, BufWriter, Cursor, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use std::io;
+
+use chrono::{Datelike, Duration, Months, TimeZone, Utc};
+use flate2::read::GzDecoder;
+use futures::future::join_all;
+use futures::stream::{self, Stream};
+use log::{debug, info, warn};
+use polars::df;
+use polars::frame::DataFrame;
+use polars::error::PolarsError;
+use polars::io::{SerReader, SerWriter};
+use polars::prelude::{
+    col, AnyValue, CsvReadOptions, CsvWriter, Expr, LazyCsvReader, LazyFileListReader,
+    ParquetWriter,
+};
+use rayon::prelude::*;
+use serde_json::json;
+
+pub mod taf;
+
+fn finite(val: f64) -> Option<f64> {
+    val.is_finite().then_some(val)
+}
+
+// Chronological, with reports missing a timestamp sorted after everything else.
+fn cmp_observation_time(a: &Metar, b: &Metar) -> Ordering {
+    match (a.observation_time, b.observation_time) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+pub fn haversine_miles(a: (f64, f64), b: (f64, f64)) -> Option<f64> {
+    let (lat1, lon1) = (a.0.to_radians(), a.1.to_radians());
+    let (lat2, lon2) = (b.0.to_radians(), b.1.to_radians());
+
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+
+    finite(2.0 * 3958.8 * h.sqrt().asin())
+}
+
+/// Initial great-circle bearing from `from` to `to`, in degrees clockwise from true north.
+pub fn bearing_degrees(from: (f64, f64), to: (f64, f64)) -> Option<f64> {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+
+    let y = (lon2 - lon1).sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * (lon2 - lon1).cos();
+
+    finite(y.atan2(x).to_degrees().rem_euclid(360.0))
+}
+
+const CARDINAL_DIRECTIONS: [&str; 17] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW",
+    "NNW", "N",
+];
+
+fn cardinal_direction(degrees: f64) -> &'static str {
+    let index = (degrees.rem_euclid(360.0) / 22.5).round();
+
+    CARDINAL_DIRECTIONS[index as usize]
+}
+
+fn verify_body(received: u64, expected: Option<u64>) -> Result<(), MetarError> {
+    if received == 0 {
+        return Err(MetarError::Download(String::from("empty response body")));
+    }
+
+    match expected {
+        Some(len) if len != received => Err(MetarError::Download(format!(
+            "expected {len} bytes but received {received}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+const METAR_CACHE_URL: &str = "https://aviationweather.gov/data/cache/metars.cache.csv.gz";
+
+// aviationweather.gov asks clients to identify themselves rather than send reqwest's default.
+const DEFAULT_USER_AGENT: &str = concat!("metars/", env!("CARGO_PKG_VERSION"));
+
+// First pause between retries; it doubles with each attempt, up to 64 times this.
+const RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_millis(500);
+
+fn http_client(
+    timeout: Option<std::time::Duration>,
+    user_agent: &str,
+) -> Result<reqwest::Client, MetarError> {
+    let mut builder = reqwest::Client::builder().user_agent(user_agent);
+
+    if let Some(val) = timeout {
+        builder = builder.timeout(val);
+    }
+
+    Ok(builder.build()?)
+}
+
+async fn with_retries<T, F, Fut>(url: &str, retries: u32, mut request: F) -> Result<T, MetarError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, MetarError>>,
+{
+    let mut attempt = 0;
+
+    loop {
+        match request().await {
+            Ok(val) => return Ok(val),
+            Err(err) if attempt < retries => {
+                let delay = RETRY_BACKOFF * 2u32.pow(attempt.min(6));
+
+                warn!("Retrying {url} in {delay:?}: {err}");
+
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+async fn fetch_bytes(
+    url: &str,
+    timeout: Option<std::time::Duration>,
+    retries: u32,
+    user_agent: &str,
+) -> Result<Vec<u8>, MetarError> {
+    let client = &http_client(timeout, user_agent)?;
+
+    info!("Downloading {url}");
+
+    let bytes = with_retries(url, retries, move || async move {
+        let resp = client.get(url).send().await?;
+
+        if resp.status() != 200 {
+            return Err(MetarError::Download(format!("HTTP {}", resp.status())));
+        }
+
+        let expected = resp.content_length();
+        let bytes = resp.bytes().await?;
+
+        verify_body(bytes.len() as u64, expected)?;
+
+        Ok(bytes)
+    })
+    .await?;
+
+    info!("Downloaded {} bytes from {url}", bytes.len());
+
+    Ok(bytes.to_vec())
+}
+
+// Feeds each chunk through the decoder as it arrives, so the archive is never held whole.
+async fn fetch_decompressed(
+    url: &str,
+    timeout: Option<std::time::Duration>,
+    retries: u32,
+    user_agent: &str,
+) -> Result<Vec<u8>, MetarError> {
+    let client = &http_client(timeout, user_agent)?;
+
+    info!("Streaming {url}");
+
+    let corrupt = |err: io::Error| {
+        MetarError::Download(format!("corrupt or truncated archive from {url}: {err}"))
+    };
+
+    let contents = with_retries(url, retries, move || async move {
+        let mut resp = client.get(url).send().await?;
+
+        if resp.status() != 200 {
+            return Err(MetarError::Download(format!("HTTP {}", resp.status())));
+        }
+
+        let expected = resp.content_length();
+        let mut received = 0;
+        let mut decoder = flate2::write::GzDecoder::new(Vec::new());
+
+        while let Some(chunk) = resp.chunk().await? {
+            received += chunk.len() as u64;
+            decoder.write_all(&chunk).map_err(corrupt)?;
+        }
+
+        verify_body(received, expected)?;
+
+        decoder.finish().map_err(corrupt)
+    })
+    .await?;
+
+    info!("Decompressed {} bytes from {url}", contents.len());
+
+    Ok(contents)
+}
+
+async fn download_file(
+    url: &str,
+    path: &str,
+    timeout: Option<std::time::Duration>,
+    retries: u32,
+    user_agent: &str,
+) -> Result<(), MetarError> {
+    let bytes = fetch_bytes(url, timeout, retries, user_agent).await?;
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+
+    io::copy(&mut bytes.as_slice(), &mut writer)?;
+
+    Ok(())
+}
+
+/// Downloads every URL concurrently with the timeout, retries and user agent from
+/// `config`; one failing URL leaves the others' results intact.
+pub async fn fetch_all(config: &FetchConfig, urls: &[&str]) -> Vec<Result<Vec<u8>, MetarError>> {
+    join_all(
+        urls.iter()
+            .map(|url| fetch_bytes(url, config.timeout, config.retries, &config.user_agent)),
+    )
+    .await
+}
+
+#[cfg(feature = "blocking")]
+fn download_file_blocking(url: &str, path: &str) -> Result<(), MetarError> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(DEFAULT_USER_AGENT)
+        .build()?;
+
+    let resp = client.get(url).send()?;
+
+    if resp.status() != 200 {
+        return Err(MetarError::Download(format!("HTTP {}", resp.status())));
+    }
+
+    let expected = resp.content_length();
+    let bytes = resp.bytes()?;
+
+    verify_body(bytes.len() as u64, expected)?;
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+
+    io::copy(&mut bytes.as_ref(), &mut writer)?;
+
+    Ok(())
+}
+
+fn is_fresh(path: &Path, ttl: std::time::Duration, now: SystemTime) -> bool {
+    fs::metadata(path)
+        .and_then(|x| x.modified())
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .is_some_and(|age| age < ttl)
+}
+
+// Another run may already have cleaned the same file up, which is not an error.
+fn remove_if_exists(path: &str) -> Result<(), MetarError> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+fn extract_file(path: &str, out_path: &str, keep_files: bool) -> Result<(), MetarError> {
+    let gz = File::open(path)?;
+    let decompressed = GzDecoder::new(gz);
+    let out = File::create(out_path)?;
+    let mut writer = BufWriter::new(out);
+
+    // Only decoder failures mean a bad archive; a failed write is an ordinary I/O error.
+    io::copy(&mut BufReader::new(decompressed), &mut writer).map_err(|err| match err.kind() {
+        io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+            MetarError::Download(format!("corrupt or truncated archive {path}: {err}"))
+        }
+        _ => MetarError::Io(err),
+    })?;
+
+    writer.flush()?;
+
+    if !keep_files {
+        remove_if_exists(path)?;
+    }
+
+    Ok(())
+}
+
+// Columns `parse_metars` reads; everything else in the feed is dropped at scan time.
+const METAR_COLUMNS: [&str; 20] = [
+    "raw_text",
+    "station_id",
+    "observation_time",
+    "latitude",
+    "longitude",
+    "temp_c",
+    "dewpoint_c",
+    "wind_dir_degrees",
+    "wind_speed_kt",
+    "wind_gust_kt",
+    "visibility_statute_mi",
+    "altim_in_hg",
+    "wx_string",
+    "sky_cover",
+    "cloud_base_ft_agl",
+    "flight_category",
+    "vert_vis_ft",
+    "metar_type",
+    "elevation_m",
+    "maintenance_indicator_on",
+];
+
+fn header_offset(contents: &str) -> Result<usize, MetarError> {
+    let mut offset = 0;
+
+    for line in contents.split_inclusive('\n') {
+        if line.starts_with("raw_text,") {
+            return Ok(offset);
+        }
+
+        offset += line.len();
+    }
+
+    Err(MetarError::Schema(String::from(
+        "missing `raw_text` header row",
+    )))
+}
+
+fn strip_preamble(path: &str) -> Result<(), MetarError> {
+    let contents = fs::read_to_string(path)?;
+
+    let offset = header_offset(&contents)?;
+
+    if offset > 0 {
+        let lines = contents[..offset].lines().count();
+
+        info!("Stripped {lines} preamble lines from {path}");
+
+        fs::write(path, &contents[offset..])?;
+    }
+
+    Ok(())
+}
+
+fn metar_projection<'a, I>(names: I) -> Result<Vec<String>, MetarError>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut columns = Vec::new();
+
+    for name in names {
+        let column = match name.split_once("_duplicated_") {
+            Some((column, _)) if column != "sky_cover" && column != "cloud_base_ft_agl" => {
+                return Err(MetarError::Schema(format!("duplicate column `{column}`")));
+            }
+            Some((column, _)) => column,
+            None => name,
+        };
+
+        if METAR_COLUMNS.contains(&column) {
+            columns.push(String::from(name));
+        }
+    }
+
+    Ok(columns)
+}
+
+fn read_cache_file(path: &str) -> Result<DataFrame, MetarError> {
+    strip_preamble(path)?;
+
+    let dataframe = CsvReadOptions::default()
+        .with_infer_schema_length(None)
+        .try_into_reader_with_file_path(Some(path.into()))?
+        .finish()?;
+
+    remove_if_exists(path)?;
+
+    Ok(dataframe)
+}
+
+fn parse_fraction(val: &str) -> Option<f64> {
+    match val.split_once('/') {
+        Some((numerator, denominator)) => {
+            let numerator = numerator.parse::<f64>().ok()?;
+            let denominator = denominator.parse::<f64>().ok()?;
+
+            if denominator == 0.0 {
+                None
+            } else {
+                Some(numerator / denominator)
+            }
+        }
+        None => val.parse::<f64>().ok(),
+    }
+}
+
+// Speeds keep the unit the group was reported in, e.g. `18010MPS` stays in m/s.
+fn parse_wind_group(group: &str) -> Option<(WindDirection, Wind, Wind)> {
+    let (group, unit): (&str, fn(Option<f64>) -> Wind) = if let Some(val) = group.strip_suffix("KT")
+    {
+        (val, Wind::Knots)
+    } else if let Some(val) = group.strip_suffix("MPS") {
+        (val, Wind::MetersPerSecond)
+    } else {
+        (group.strip_suffix("KMH")?, Wind::KilometersPerHour)
+    };
+
+    if group.len() < 5 || !group.is_ascii() {
+        return None;
+    }
+
+    let (direction, speed) = group.split_at(3);
+
+    let wind_dir_degrees = match direction {
+        "VRB" => WindDirection::Variable(Some(String::from("VRB"))),
+        _ => WindDirection::Degrees(Some(direction.parse::<i32>().ok()?)),
+    };
+
+    let (speed, gust) = match speed.split_once('G') {
+        Some((speed, gust)) => (speed, Some(gust.parse::<f64>().ok()?)),
+        None => (speed, None),
+    };
+
+    let wind_speed = unit(Some(speed.parse::<f64>().ok()?));
+
+    Some((wind_dir_degrees, wind_speed, unit(gust)))
+}
+
+fn parse_visibility_group(group: &str) -> Option<f64> {
+    if let Some(val) = group.strip_suffix("SM") {
+        let val = val.trim_start_matches(['P', 'M']);
+
+        return parse_fraction(val);
+    }
+
+    if group.len() == 4 && group.bytes().all(|x| x.is_ascii_digit()) {
+        let meters = group.parse::<f64>().ok()?;
+
+        return Some((meters / 1609.344 * 100.0).round() / 100.0);
+    }
+
+    None
+}
+
+// The feed marks a floor as `10+`, and may carry raw forms such as `M1/4` or `1 1/2`.
+fn parse_visibility_value(val: &str) -> (Option<f64>, Option<VisibilityQualifier>) {
+    let (val, qualifier) = if let Some(val) = val.strip_suffix('+') {
+        (val, Some(VisibilityQualifier::GreaterThan))
+    } else if let Some(val) = val.strip_prefix('P') {
+        (val, Some(VisibilityQualifier::GreaterThan))
+    } else if let Some(val) = val.strip_prefix('M') {
+        (val, Some(VisibilityQualifier::LessThan))
+    } else {
+        (val, None)
+    };
+
+    let statute_mi = match val.trim().split_once(' ') {
+        Some((whole, fraction)) => whole
+            .parse::<f64>()
+            .ok()
+            .zip(parse_fraction(fraction))
+            .map(|(whole, fraction)| whole + fraction),
+        None => parse_fraction(val.trim()),
+    };
+
+    (statute_mi, qualifier)
+}
+
+fn has_cavok_group(raw_text: &str) -> bool {
+    raw_text
+        .split_whitespace()
+        .take_while(|&x| x != "RMK")
+        .any(|x| x == "CAVOK")
+}
+
+// Raw groups encode negative values with an `M` prefix (M05 is -5), while a bare
+// `M` marks the value as missing.
+fn parse_temperature(val: &str) -> Option<f64> {
+    match val.strip_prefix('M') {
+        Some("") => None,
+        Some(val) => val.parse::<f64>().ok().map(|x| -x),
+        None => val.parse::<f64>().ok(),
+    }
+}
+
+// Typed columns are read straight off the value; going through `str_value` would
+// format each number into a fresh String only to parse it back.
+fn value_str<'a>(val: &'a AnyValue) -> Option<&'a str> {
+    match val {
+        AnyValue::String(x) => Some(x),
+        AnyValue::StringOwned(x) => Some(x.as_str()),
+        _ => None,
+    }
+}
+
+fn value_f64(val: &AnyValue) -> Option<f64> {
+    value_str(val).map_or_else(|| val.extract::<f64>(), |x| x.parse::<f64>().ok())
+}
+
+fn value_i32(val: &AnyValue) -> Option<i32> {
+    value_str(val).map_or_else(|| val.extract::<i32>(), |x| x.parse::<i32>().ok())
+}
+
+fn value_temperature(val: &AnyValue) -> Option<f64> {
+    value_str(val).map_or_else(|| val.extract::<f64>(), parse_temperature)
+}
+
+// Null and the feed's bare `M` are plain missing values; anything else that fails to
+// parse is logged, so a malformed column doesn't silently turn into `None`.
+fn parse_column<T, P>(station_id: &str, field: &str, val: &AnyValue, parse: P) -> Option<T>
+where
+    P: Fn(&AnyValue) -> Option<T>,
+{
+    if val.is_null() || value_str(val) == Some("M") {
+        return None;
+    }
+
+    let parsed = parse(val);
+
+    if parsed.is_none() {
+        warn!("{station_id}: dropped malformed {field} `{val}`");
+    }
+
+    parsed
+}
+
+fn parse_temperature_dewpoint_group(raw_text: &str) -> Option<(Option<f64>, Option<f64>)> {
+    let is_temperature = |val: &str| {
+        let digits = val.strip_prefix('M').unwrap_or(val);
+        digits.len() == 2 && digits.bytes().all(|x| x.is_ascii_digit())
+    };
+
+    raw_text
+        .split_whitespace()
+        .take_while(|&x| x != "RMK")
+        .filter_map(|group| group.split_once('/'))
+        .find(|(temp, dewpoint)| {
+            is_temperature(temp) && (dewpoint.is_empty() || is_temperature(dewpoint))
+        })
+        .map(|(temp, dewpoint)| (parse_temperature(temp), parse_temperature(dewpoint)))
+}
+
+// The `TsTTTsDDD` remark carries tenths of a degree, with a leading 1 marking a
+// negative value, e.g. T00121008 is 1.2°C over -0.8°C.
+fn parse_precise_temperature_group(raw_text: &str) -> Option<(Option<f64>, Option<f64>)> {
+    let parse_tenths = |val: &str| {
+        let tenths = val[1..].parse::<f64>().ok()? / 10.0;
+
+        match &val[..1] {
+            "0" => Some(tenths),
+            "1" => Some(-tenths),
+            _ => None,
+        }
+    };
+
+    raw_text
+        .split_whitespace()
+        .skip_while(|&x| x != "RMK")
+        .filter_map(|group| group.strip_prefix('T'))
+        .filter(|group| matches!(group.len(), 4 | 8) && group.bytes().all(|x| x.is_ascii_digit()))
+        .find_map(|group| {
+            let temp = parse_tenths(&group[..4])?;
+            let dewpoint = group
+                .get(4..)
+                .filter(|x| !x.is_empty())
+                .and_then(parse_tenths);
+
+            Some((Some(temp), dewpoint))
+        })
+}
+
+const WEATHER_CODES: [(&str, &str); 30] = [
+    ("MI", "shallow"),
+    ("PR", "partial"),
+    ("BC", "patches of"),
+    ("DR", "low drifting"),
+    ("BL", "blowing"),
+    ("SH", "showers"),
+    ("TS", "thunderstorm"),
+    ("FZ", "freezing"),
+    ("DZ", "drizzle"),
+    ("RA", "rain"),
+    ("SN", "snow"),
+    ("SG", "snow grains"),
+    ("IC", "ice crystals"),
+    ("PL", "ice pellets"),
+    ("GR", "hail"),
+    ("GS", "small hail"),
+    ("UP", "unknown precipitation"),
+    ("BR", "mist"),
+    ("FG", "fog"),
+    ("FU", "smoke"),
+    ("VA", "volcanic ash"),
+    ("DU", "dust"),
+    ("SA", "sand"),
+    ("HZ", "haze"),
+    ("PY", "spray"),
+    ("PO", "dust whirls"),
+    ("SQ", "squalls"),
+    ("FC", "funnel cloud"),
+    ("SS", "sandstorm"),
+    ("DS", "duststorm"),
+];
+
+fn is_weather_group(group: &str) -> bool {
+    if group == "NSW" {
+        return true;
+    }
+
+    let group = group.trim_start_matches(['+', '-']);
+    let group = group.strip_prefix("VC").unwrap_or(group);
+
+    !group.is_empty()
+        && group.is_ascii()
+        && group.len().is_multiple_of(2)
+        && group
+            .as_bytes()
+            .chunks(2)
+            .all(|x| WEATHER_CODES.iter().any(|(code, _)| code.as_bytes() == x))
+}
+
+// Compares whole two-letter codes, so `DUPO` is dust and dust whirls rather than `UP`.
+fn weather_group_has(group: &str, codes: &[&str]) -> bool {
+    if group == "NSW" || !is_weather_group(group) {
+        return false;
+    }
+
+    let group = group.trim_start_matches(['+', '-']);
+    let group = group.strip_prefix("VC").unwrap_or(group);
+
+    group
+        .as_bytes()
+        .chunks(2)
+        .any(|x| codes.iter().any(|code| code.as_bytes() == x))
+}
+
+fn is_thunderstorm_group(group: &str) -> bool {
+    weather_group_has(group, &["TS"])
+}
+
+fn decode_weather_group(group: &str) -> Option<String> {
+    if !is_weather_group(group) {
+        return None;
+    }
+
+    if group == "NSW" {
+        return Some(String::from("no significant weather"));
+    }
+
+    let mut words = Vec::new();
+
+    let group = if let Some(val) = group.strip_prefix('-') {
+        words.push("light");
+        val
+    } else if let Some(val) = group.strip_prefix('+') {
+        words.push("heavy");
+        val
+    } else {
+        group
+    };
+
+    let (vicinity, group) = match group.strip_prefix("VC") {
+        Some(val) => (true, val),
+        None => (false, group),
+    };
+
+    let mut showers = false;
+
+    for code in group.as_bytes().chunks(2) {
+        if code == b"SH" {
+            showers = true;
+            continue;
+        }
+
+        if let Some((_, description)) = WEATHER_CODES.iter().find(|(x, _)| x.as_bytes() == code) {
+            words.push(description);
+        }
+    }
+
+    if showers {
+        words.push("showers");
+    }
+
+    if vicinity {
+        words.push("in the vicinity");
+    }
+
+    Some(words.join(" "))
+}
+
+// Raw reports write negative temperatures with an `M` prefix, e.g. -5 as M05.
+fn format_temperature_group(val: f64) -> String {
+    let val = val.round() as i32;
+
+    if val < 0 {
+        format!("M{:02}", -val)
+    } else {
+        format!("{val:02}")
+    }
+}
+
+// 1.5 becomes "1 1/2"; values that are not an exact sixteenth keep their decimals.
+// Only sixteenths can be written as a statute-mile group.
+fn format_statute_miles(val: f64) -> Option<String> {
+    let whole = val.trunc();
+    let fraction = val - whole;
+
+    if fraction == 0.0 {
+        return Some(format!("{whole}"));
+    }
+
+    let denominator = [2.0, 4.0, 8.0, 16.0]
+        .into_iter()
+        .find(|x: &f64| ((fraction * x) - (fraction * x).round()).abs() < 1e-9)?;
+
+    let numerator = (fraction * denominator).round();
+
+    if whole == 0.0 {
+        Some(format!("{numerator}/{denominator}"))
+    } else {
+        Some(format!("{whole} {numerator}/{denominator}"))
+    }
+}
+
+// Metric groups step by 50 m below 800 m, 100 m up to 5 km and 1 km above, topping out
+// at 9999 for 10 km or more.
+fn format_meters(val: f64) -> String {
+    let step = match val {
+        val if val < 800.0 => 50.0,
+        val if val <= 5000.0 => 100.0,
+        _ => 1000.0,
+    };
+
+    let meters = ((val / step).round() * step).min(9999.0);
+
+    format!("{meters:04.0}")
+}
+
+// `RERA`, `REFZRA` and the like describe weather that ended since the last report.
+fn parse_recent_weather(raw_text: &str) -> Vec<String> {
+    raw_text
+        .split_whitespace()
+        .take_while(|&x| x != "RMK")
+        .filter_map(|x| x.strip_prefix("RE"))
+        .filter_map(decode_weather_group)
+        .map(|x| format!("Recent {x}"))
+        .collect()
+}
+
+fn format_thousands(val: i32) -> String {
+    let digits = val.unsigned_abs().to_string();
+    let mut formatted = String::new();
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+
+        formatted.push(digit);
+    }
+
+    if val < 0 {
+        formatted.insert(0, '-');
+    }
+
+    formatted
+}
+
+fn cardinal_direction_name(abbreviation: &str) -> String {
+    let name = |val: char| match val {
+        'N' => "north",
+        'E' => "east",
+        'S' => "south",
+        'W' => "west",
+        _ => "",
+    };
+
+    match abbreviation.len() {
+        3 => {
+            let mut chars = abbreviation.chars();
+            let first = chars.next().map_or("", name);
+            let rest: String = chars.map(name).collect();
+
+            format!("{first}-{rest}")
+        }
+        _ => abbreviation.chars().map(name).collect(),
+    }
+}
+
+#[derive(Debug)]
+pub enum MetarError {
+    Io(io::Error),
+    Http(reqwest::Error),
+    Download(String),
+    Polars(PolarsError),
+    Schema(String),
+}
+
+impl fmt::Display for MetarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+            Self::Http(err) => write!(f, "HTTP request failed: {err}"),
+            Self::Download(msg) => write!(f, "Failed to download file: {msg}"),
+            Self::Polars(err) => write!(f, "Failed to read CSV: {err}"),
+            Self::Schema(msg) => write!(f, "Unexpected CSV schema: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for MetarError {}
+
+impl From<io::Error> for MetarError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<reqwest::Error> for MetarError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Http(err)
+    }
+}
+
+impl From<PolarsError> for MetarError {
+    fn from(err: PolarsError) -> Self {
+        Self::Polars(err)
+    }
+}
+
+fn nearest_day_time(
+    reference: chrono::DateTime<Utc>,
+    day: u32,
+    hour: u32,
+    minute: u32,
+) -> Option<chrono::DateTime<Utc>> {
+    if !(1..=31).contains(&day) || hour > 24 || minute > 59 {
+        return None;
+    }
+
+    let first_of_month = Utc
+        .with_ymd_and_hms(reference.year(), reference.month(), 1, 0, 0, 0)
+        .single()?;
+
+    let months = [
+        first_of_month.checked_sub_months(Months::new(1))?,
+        first_of_month,
+        first_of_month.checked_add_months(Months::new(1))?,
+    ];
+
+    months
+        .iter()
+        .filter_map(|month| {
+            let date = month.with_day(day)?;
+            Some(date + Duration::hours(i64::from(hour)) + Duration::minutes(i64::from(minute)))
+        })
+        .min_by_key(|date| (*date - reference).num_seconds().abs())
+}
+
+fn parse_time_group(
+    raw_text: &str,
+    reference: chrono::DateTime<Utc>,
+) -> Option<chrono::DateTime<Utc>> {
+    let group = raw_text
+        .split_whitespace()
+        .take(3)
+        .find(|x| x.len() == 7 && x.ends_with('Z'))?;
+
+    if !group[..6].bytes().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+
+    let day = group[..2].parse::<u32>().ok()?;
+    let hour = group[2..4].parse::<u32>().ok()?;
+    let minute = group[4..6].parse::<u32>().ok()?;
+
+    nearest_day_time(reference, day, hour, minute)
+}
+
+fn parse_validity_period(
+    group: &str,
+    reference: chrono::DateTime<Utc>,
+) -> Option<(chrono::DateTime<Utc>, chrono::DateTime<Utc>)> {
+    let (start, end) = group.split_once('/')?;
+
+    if start.len() != 4 || end.len() != 4 {
+        return None;
+    }
+
+    let start_day = start[..2].parse::<u32>().ok()?;
+    let start_hour = start[2..].parse::<u32>().ok()?;
+    let end_day = end[..2].parse::<u32>().ok()?;
+    let end_hour = end[2..].parse::<u32>().ok()?;
+
+    let start = nearest_day_time(reference, start_day, start_hour, 0)?;
+    let end = nearest_day_time(start, end_day, end_hour, 0)?;
+
+    if end < start {
+        return None;
+    }
+
+    Some((start, end))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Temperature {
+    Celsius(Option<f64>),
+    Fahrenheit(Option<f64>),
+}
+
+impl Temperature {
+    pub fn to_fahrenheit(&self) -> Option<f64> {
+        match *self {
+            Self::Celsius(Some(val)) => finite(val.mul_add(1.8, 32.0)),
+            Self::Fahrenheit(Some(val)) => finite(val),
+            _ => None,
+        }
+    }
+
+    pub fn to_celsius(&self) -> Option<f64> {
+        match *self {
+            Self::Celsius(Some(val)) => finite(val),
+            Self::Fahrenheit(Some(val)) => finite((val - 32.0) / 1.8),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindDirection {
+    Degrees(Option<i32>),
+    Variable(Option<String>),
+}
+
+impl WindDirection {
+    pub fn to_cardinal_direction(&self) -> Option<String> {
+        match *self {
+            Self::Degrees(Some(val)) => {
+                if val == 0 {
+                    None
+                } else {
+                    Some(String::from(cardinal_direction(f64::from(val))))
+                }
+            }
+            Self::Variable(_) => Some(String::from("Variable")),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Wind {
+    Knots(Option<f64>),
+    Mph(Option<f64>),
+    MetersPerSecond(Option<f64>),
+    KilometersPerHour(Option<f64>),
+}
+
+impl Wind {
+    pub fn to_mph(&self) -> Option<f64> {
+        match *self {
+            Self::Knots(Some(val)) => {
+                let result = val * 1.15078;
+                finite((result * 100.00).floor() / 100.0)
+            }
+            Self::Mph(Some(val)) => finite(val),
+            Self::MetersPerSecond(Some(val)) => finite((val * 223.694).floor() / 100.0),
+            Self::KilometersPerHour(Some(val)) => finite((val * 62.1371).floor() / 100.0),
+            _ => None,
+        }
+    }
+
+    pub fn to_knots(&self) -> Option<f64> {
+        match *self {
+            Self::Knots(Some(val)) => finite(val),
+            Self::Mph(Some(val)) => finite((val / 1.15078).round()),
+            Self::MetersPerSecond(Some(val)) => finite((val * 1.943_84).round()),
+            Self::KilometersPerHour(Some(val)) => finite((val / 1.852).round()),
+            _ => None,
+        }
+    }
+
+    // Raw value and unit suffix for a wind group; mph has no METAR form, so it goes out in knots.
+    fn group_value(&self) -> Option<(f64, &'static str)> {
+        match *self {
+            Self::MetersPerSecond(Some(val)) => finite(val).map(|x| (x, "MPS")),
+            Self::KilometersPerHour(Some(val)) => finite(val).map(|x| (x, "KMH")),
+            _ => self.to_knots().map(|x| (x, "KT")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cloud {
+    pub sky_cover: Option<String>,
+    pub sky_cover_label: Option<String>,
+    pub cloud_base_ft_agl: Option<i32>,
+    pub convective_type: Option<String>,
+    pub convective_type_label: Option<String>,
+}
+
+impl Cloud {
+    fn sky_cover_label(&mut self) {
+        let sky_cover_label = match &self.sky_cover {
+            Some(val) => match val.as_ref() {
+                "CLR" | "SKC" => Some(String::from("Clear")),
+                "FEW" => Some(String::from("Few")),
+                "SCT" => Some(String::from("Scattered")),
+                "BKN" => Some(String::from("Broken")),
+                "OVC" => Some(String::from("Overcast")),
+                "OVX" => Some(String::from("Obscured")),
+                "VV" => Some(String::from("Vertical Visibility")),
+                _ => Some(String::new()),
+            },
+            None => None,
+        };
+
+        self.sky_cover_label = sky_cover_label;
+    }
+
+    fn convective_type_label(&mut self) {
+        let convective_type_label = match &self.convective_type {
+            Some(val) => match val.as_ref() {
+                "CB" => Some(String::from("Cumulonimbus")),
+                "TCU" => Some(String::from("Towering Cumulus")),
+                _ => Some(String::new()),
+            },
+            None => None,
+        };
+
+        self.convective_type_label = convective_type_label;
+    }
+
+    pub fn base_ft_msl(&self, field_elevation_ft: Option<f64>) -> Option<f64> {
+        match (self.cloud_base_ft_agl, field_elevation_ft) {
+            (Some(base), Some(elevation)) => Some(f64::from(base) + elevation),
+            _ => None,
+        }
+    }
+
+    fn parse_group(group: &str) -> Option<Self> {
+        let (sky_cover, cloud_base, convective_type) = match group {
+            "CLR" | "SKC" => (group, None, None),
+            _ if group.len() == 5 && group.starts_with("VV") => {
+                ("VV", group[2..].parse::<i32>().ok().map(|x| x * 100), None)
+            }
+            _ if group.len() >= 6 && group.is_ascii() => {
+                let (sky_cover, rest) = group.split_at(3);
+
+                if !["FEW", "SCT", "BKN", "OVC"].contains(&sky_cover) {
+                    return None;
+                }
+
+                let (cloud_base, convective_type) = rest.split_at(3);
+
+                let convective_type = match convective_type {
+                    "CB" | "TCU" => Some(String::from(convective_type)),
+                    _ => None,
+                };
+
+                (
+                    sky_cover,
+                    Some(cloud_base.parse::<i32>().ok()? * 100),
+                    convective_type,
+                )
+            }
+            _ => return None,
+        };
+
+        let mut cloud = Self {
+            sky_cover: Some(String::from(sky_cover)),
+            cloud_base_ft_agl: cloud_base,
+            sky_cover_label: None,
+            convective_type,
+            convective_type_label: None,
+        };
+
+        cloud.sky_cover_label();
+        cloud.convective_type_label();
+
+        Some(cloud)
+    }
+
+    fn to_group(&self) -> Option<String> {
+        let mut group = self.sky_cover.clone()?;
+
+        if let Some(val) = self.cloud_base_ft_agl {
+            group.push_str(&format!("{:03}", val / 100));
+        }
+
+        if let Some(val) = &self.convective_type {
+            group.push_str(val);
+        }
+
+        Some(group)
+    }
+
+    fn parse_raw_layers(raw_text: &str) -> Vec<Self> {
+        raw_text
+            .split_whitespace()
+            .take_while(|&x| x != "RMK")
+            .filter_map(Self::parse_group)
+            .collect()
+    }
+}
+
+/// An `RVRNO` remark decodes to an entry with `not_available` set, no runway and no
+/// range, so it stays distinct from a report without any RVR groups.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunwayVisualRange {
+    pub runway: String,
+    pub min_ft: Option<i32>,
+    pub max_ft: Option<i32>,
+    pub less_than: bool,
+    pub greater_than: bool,
+    pub trend: Option<String>,
+    pub trend_label: Option<String>,
+    pub not_available: bool,
+}
+
+impl RunwayVisualRange {
+    fn trend_label(&mut self) {
+        let trend_label = match &self.trend {
+            Some(val) => match val.as_ref() {
+                "U" => Some(String::from("Upward")),
+                "D" => Some(String::from("Downward")),
+                "N" => Some(String::from("No Change")),
+                _ => Some(String::new()),
+            },
+            None => None,
+        };
+
+        self.trend_label = trend_label;
+    }
+
+    fn parse_range(val: &str, feet: bool) -> Option<(i32, Option<char>)> {
+        let (prefix, digits) = match val.strip_prefix(['P', 'M']) {
+            Some(rest) => (val.chars().next(), rest),
+            None => (None, val),
+        };
+
+        if digits.len() != 4 || !digits.bytes().all(|x| x.is_ascii_digit()) {
+            return None;
+        }
+
+        let range = digits.parse::<i32>().ok()?;
+
+        if feet {
+            Some((range, prefix))
+        } else {
+            Some(((f64::from(range) * 3.28084).round() as i32, prefix))
+        }
+    }
+
+    fn parse_group(group: &str) -> Option<Self> {
+        let (runway, range) = group.strip_prefix('R')?.split_once('/')?;
+
+        let number = runway.trim_end_matches(['L', 'C', 'R']);
+
+        if number.len() != 2 || runway.len() > 3 || !number.bytes().all(|x| x.is_ascii_digit()) {
+            return None;
+        }
+
+        let (range, trend) = match range.strip_suffix(['U', 'D', 'N']) {
+            Some(rest) => (rest.trim_end_matches('/'), range.get(range.len() - 1..)),
+            None => (range, None),
+        };
+
+        let (range, feet) = match range.strip_suffix("FT") {
+            Some(rest) => (rest, true),
+            None => (range, false),
+        };
+
+        let (min, max) = range.split_once('V').unwrap_or((range, range));
+
+        let (min_ft, min_prefix) = Self::parse_range(min, feet)?;
+        let (max_ft, max_prefix) = Self::parse_range(max, feet)?;
+
+        let mut rvr = Self {
+            runway: String::from(runway),
+            min_ft: Some(min_ft),
+            max_ft: Some(max_ft),
+            less_than: min_prefix == Some('M'),
+            greater_than: max_prefix == Some('P'),
+            trend: trend.map(String::from),
+            trend_label: None,
+            not_available: false,
+        };
+
+        rvr.trend_label();
+
+        Some(rvr)
+    }
+
+    fn not_available() -> Self {
+        Self {
+            runway: String::new(),
+            min_ft: None,
+            max_ft: None,
+            less_than: false,
+            greater_than: false,
+            trend: None,
+            trend_label: None,
+            not_available: true,
+        }
+    }
+
+    fn parse_raw_groups(raw_text: &str) -> Vec<Self> {
+        let mut groups = raw_text.split_whitespace();
+
+        let mut rvr: Vec<Self> = groups
+            .by_ref()
+            .take_while(|&x| x != "RMK")
+            .filter_map(Self::parse_group)
+            .collect();
+
+        if groups.any(|x| x == "RVRNO") {
+            rvr.push(Self::not_available());
+        }
+
+        rvr
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Elevation {
+    Meters(Option<f64>),
+    Feet(Option<f64>),
+}
+
+// The feed's placeholder for a station without a surveyed elevation.
+const MISSING_ELEVATION_M: f64 = 9999.0;
+
+impl Elevation {
+    pub fn to_feet(&self) -> Option<f64> {
+        match *self {
+            Self::Meters(Some(val)) if val == MISSING_ELEVATION_M => None,
+            Self::Meters(Some(val)) => finite((val * 3.28084).round()),
+            Self::Feet(Some(val)) => finite(val),
+            _ => None,
+        }
+    }
+
+    pub fn to_meters(&self) -> Option<f64> {
+        match *self {
+            Self::Meters(Some(val)) if val == MISSING_ELEVATION_M => None,
+            Self::Meters(Some(val)) => finite(val),
+            Self::Feet(Some(val)) => finite((val / 3.28084).round()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReportType {
+    Metar,
+    Speci,
+    Unknown(String),
+}
+
+impl ReportType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Metar => "METAR",
+            Self::Speci => "SPECI",
+            Self::Unknown(val) => val,
+        }
+    }
+
+    pub fn is_special(&self) -> bool {
+        *self == Self::Speci
+    }
+}
+
+impl From<&str> for ReportType {
+    fn from(val: &str) -> Self {
+        match val {
+            "METAR" => Self::Metar,
+            "SPECI" => Self::Speci,
+            _ => Self::Unknown(String::from(val)),
+        }
+    }
+}
+
+impl fmt::Display for ReportType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindShear {
+    pub runway: Option<String>,
+    pub all_runways: bool,
+    pub height_ft: Option<i32>,
+    pub wind_dir_degrees: WindDirection,
+    pub wind_speed_kt: Wind,
+}
+
+impl WindShear {
+    fn new() -> Self {
+        Self {
+            runway: None,
+            all_runways: false,
+            height_ft: None,
+            wind_dir_degrees: WindDirection::Degrees(None),
+            wind_speed_kt: Wind::Knots(None),
+        }
+    }
+
+    // Handles both `WS020/24045KT` and the runway forms `WS R28L` / `WS ALL RWY`.
+    fn parse_raw_groups(raw_text: &str) -> Vec<Self> {
+        let groups: Vec<&str> = raw_text
+            .split_whitespace()
+            .take_while(|&x| x != "RMK")
+            .collect();
+
+        let mut wind_shear = Vec::new();
+
+        for (i, &group) in groups.iter().enumerate() {
+            let mut shear = Self::new();
+
+            if let Some((height, wind)) = group.strip_prefix("WS").and_then(|x| x.split_once('/')) {
+                let Some((wind_dir_degrees, wind_speed_kt, _)) = parse_wind_group(wind) else {
+                    continue;
+                };
+
+                shear.height_ft = height.parse::<i32>().ok().map(|x| x * 100);
+                shear.wind_dir_degrees = wind_dir_degrees;
+                shear.wind_speed_kt = wind_speed_kt;
+            } else if group == "WS" {
+                match groups.get(i + 1..i + 3) {
+                    Some(["ALL", "RWY"]) => shear.all_runways = true,
+                    _ => {
+                        let runway = groups
+                            .get(i + 1)
+                            .and_then(|x| x.strip_prefix("RWY").or_else(|| x.strip_prefix('R')));
+
+                        match runway {
+                            Some(val) if !val.is_empty() => shear.runway = Some(String::from(val)),
+                            _ => continue,
+                        }
+                    }
+                }
+            } else {
+                continue;
+            }
+
+            wind_shear.push(shear);
+        }
+
+        wind_shear
+    }
+}
+
+/// Peak wind since the last report; `hour` is `None` when the remark only gives minutes
+/// past the observation hour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeakWind {
+    pub wind_dir_degrees: WindDirection,
+    pub wind_speed_kt: Wind,
+    pub hour: Option<u32>,
+    pub minute: u32,
+}
+
+impl PeakWind {
+    // Decodes `PK WND dddff(f)/(hh)mm`.
+    fn parse_remarks(remarks: &str) -> Option<Self> {
+        let groups: Vec<&str> = remarks.split_whitespace().collect();
+
+        let index = groups.windows(2).position(|x| x == ["PK", "WND"])?;
+        let (wind, time) = groups.get(index + 2)?.split_once('/')?;
+
+        if !(5..=6).contains(&wind.len()) || !wind.bytes().all(|x| x.is_ascii_digit()) {
+            return None;
+        }
+
+        if !matches!(time.len(), 2 | 4) || !time.bytes().all(|x| x.is_ascii_digit()) {
+            return None;
+        }
+
+        let (hour, minute) = match time.split_at(time.len() - 2) {
+            ("", minute) => (None, minute.parse::<u32>().ok()?),
+            (hour, minute) => (Some(hour.parse::<u32>().ok()?), minute.parse::<u32>().ok()?),
+        };
+
+        if hour.is_some_and(|x| x > 23) || minute > 59 {
+            return None;
+        }
+
+        Some(Self {
+            wind_dir_degrees: WindDirection::Degrees(wind[..3].parse::<i32>().ok()),
+            wind_speed_kt: Wind::Knots(wind[3..].parse::<f64>().ok()),
+            hour,
+            minute,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrecipIntensity {
+    Light,
+    Moderate,
+    Heavy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrecipType {
+    Drizzle,
+    Rain,
+    Snow,
+    SnowGrains,
+    IceCrystals,
+    IcePellets,
+    Hail,
+    SmallHail,
+    Unknown,
+}
+
+impl PrecipType {
+    fn from_code(code: &[u8]) -> Option<Self> {
+        let precip_type = match code {
+            b"DZ" => Self::Drizzle,
+            b"RA" => Self::Rain,
+            b"SN" => Self::Snow,
+            b"SG" => Self::SnowGrains,
+            b"IC" => Self::IceCrystals,
+            b"PL" => Self::IcePellets,
+            b"GR" => Self::Hail,
+            b"GS" => Self::SmallHail,
+            b"UP" => Self::Unknown,
+            _ => return None,
+        };
+
+        Some(precip_type)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Precip {
+    pub intensity: PrecipIntensity,
+    pub types: Vec<PrecipType>,
+    pub showers: bool,
+    pub freezing: bool,
+    pub vicinity: bool,
+}
+
+impl Precip {
+    // A bare `VCSH` counts as precipitation even though it names no type.
+    fn parse_group(group: &str) -> Option<Self> {
+        if group == "NSW" || !is_weather_group(group) {
+            return None;
+        }
+
+        let (intensity, group) = if let Some(val) = group.strip_prefix('-') {
+            (PrecipIntensity::Light, val)
+        } else if let Some(val) = group.strip_prefix('+') {
+            (PrecipIntensity::Heavy, val)
+        } else {
+            (PrecipIntensity::Moderate, group)
+        };
+
+        let (vicinity, group) = match group.strip_prefix("VC") {
+            Some(val) => (true, val),
+            None => (false, group),
+        };
+
+        let codes: Vec<&[u8]> = group.as_bytes().chunks(2).collect();
+        let types: Vec<PrecipType> = codes
+            .iter()
+            .filter_map(|x| PrecipType::from_code(x))
+            .collect();
+        let showers = codes.contains(&b"SH".as_slice());
+
+        if types.is_empty() && !showers {
+            return None;
+        }
+
+        Some(Self {
+            intensity,
+            types,
+            showers,
+            freezing: codes.contains(&b"FZ".as_slice()),
+            vicinity,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisibilityQualifier {
+    GreaterThan,
+    LessThan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Visibility {
+    StatuteMiles(Option<f64>, Option<VisibilityQualifier>),
+    Meters(Option<f64>, Option<VisibilityQualifier>),
+}
+
+impl Visibility {
+    pub fn to_statute_miles(&self) -> Option<f64> {
+        match *self {
+            Self::StatuteMiles(Some(val), _) => finite(val),
+            Self::Meters(Some(val), _) => finite((val / 1609.344 * 100.0).round() / 100.0),
+            _ => None,
+        }
+    }
+
+    pub fn to_meters(&self) -> Option<f64> {
+        match *self {
+            Self::StatuteMiles(Some(val), _) => finite((val * 1609.344).round()),
+            Self::Meters(Some(val), _) => finite(val),
+            _ => None,
+        }
+    }
+
+    pub fn qualifier(&self) -> Option<VisibilityQualifier> {
+        match *self {
+            Self::StatuteMiles(_, qualifier) | Self::Meters(_, qualifier) => qualifier,
+        }
+    }
+
+    fn parse_group(group: &str) -> Option<Self> {
+        let val = parse_visibility_group(group)?;
+
+        if group.ends_with("SM") {
+            let qualifier = match group.chars().next() {
+                Some('P') => Some(VisibilityQualifier::GreaterThan),
+                Some('M') => Some(VisibilityQualifier::LessThan),
+                _ => None,
+            };
+
+            return Some(Self::StatuteMiles(Some(val), qualifier));
+        }
+
+        let meters = group.parse::<f64>().ok()?;
+        let qualifier = (group == "9999").then_some(VisibilityQualifier::GreaterThan);
+
+        Some(Self::Meters(Some(meters), qualifier))
+    }
+
+    // A metric group keeps the meters it reported; converting back from the rounded
+    // statute miles would turn 8000 into 7998.
+    fn meters_from(raw: Option<&Self>, statute_mi: &Self) -> Self {
+        match raw {
+            Some(&Self::Meters(Some(val), qualifier)) => Self::Meters(Some(val), qualifier),
+            _ => Self::Meters(statute_mi.to_meters(), statute_mi.qualifier()),
+        }
+    }
+
+    fn parse_raw_group(raw_text: &str) -> Option<Self> {
+        let groups: Vec<&str> = raw_text
+            .split_whitespace()
+            .take_while(|&x| x != "RMK")
+            .collect();
+
+        groups.iter().enumerate().find_map(|(i, group)| {
+            let visibility = Self::parse_group(group)?;
+
+            // "1 1/2SM" splits the whole miles into a group of their own.
+            let whole = match i.checked_sub(1).map(|x| groups[x]) {
+                Some(val) if val.len() == 1 && group.contains('/') => val.parse::<f64>().ok(),
+                _ => None,
+            };
+
+            match (visibility, whole) {
+                (Self::StatuteMiles(Some(val), qualifier), Some(whole)) => {
+                    Some(Self::StatuteMiles(Some(whole + val), qualifier))
+                }
+                (visibility, _) => Some(visibility),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metar {
+    pub raw_text: String,
+    pub station_id: String,
+    pub observation_time: Option<chrono::DateTime<Utc>>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub temp_c: Temperature,
+    pub temp_f: Temperature,
+    pub dewpoint_c: Temperature,
+    pub dewpoint_f: Temperature,
+    pub wind_dir_degrees: WindDirection,
+    pub wind_dir_cardinal: Option<String>,
+    pub wind_speed_kt: Wind,
+    pub wind_speed_mph: Wind,
+    pub wind_gust_kt: Wind,
+    pub wind_gust_mph: Wind,
+    pub visibility_statute_mi: Visibility,
+    pub visibility_m: Visibility,
+    pub clouds: Vec<Cloud>,
+    pub rvr: Vec<RunwayVisualRange>,
+    pub wind_shear: Vec<WindShear>,
+    pub recent_weather: Vec<String>,
+    pub altim_in_hg: Option<f64>,
+    pub wx_string: Option<String>,
+    pub flight_category: Option<String>,
+    pub report_type: Option<ReportType>,
+    pub elevation_m: Elevation,
+    pub elevation_ft: Elevation,
+    pub remarks: Option<String>,
+    /// The feed's `maintenance_indicator_on` flag, when the column was present and set.
+    pub maintenance_indicator: Option<bool>,
+}
+
+impl Default for Metar {
+    fn default() -> Self {
+        Self {
+            raw_text: String::new(),
+            station_id: String::new(),
+            observation_time: None,
+            lat: None,
+            lon: None,
+            temp_c: Temperature::Celsius(None),
+            temp_f: Temperature::Fahrenheit(None),
+            dewpoint_c: Temperature::Celsius(None),
+            dewpoint_f: Temperature::Fahrenheit(None),
+            wind_dir_degrees: WindDirection::Degrees(None),
+            wind_dir_cardinal: None,
+            wind_speed_kt: Wind::Knots(None),
+            wind_speed_mph: Wind::Mph(None),
+            wind_gust_kt: Wind::Knots(None),
+            wind_gust_mph: Wind::Mph(None),
+            visibility_statute_mi: Visibility::StatuteMiles(None, None),
+            visibility_m: Visibility::Meters(None, None),
+            clouds: Vec::new(),
+            rvr: Vec::new(),
+            wind_shear: Vec::new(),
+            recent_weather: Vec::new(),
+            altim_in_hg: None,
+            wx_string: None,
+            flight_category: None,
+            report_type: None,
+            elevation_m: Elevation::Meters(None),
+            elevation_ft: Elevation::Feet(None),
+            remarks: None,
+            maintenance_indicator: None,
+        }
+    }
+}
+
+/// Builds a [`Metar`] by hand, keeping paired fields such as `temp_c`/`temp_f` in sync.
+///
+/// ```
+/// use metars::MetarBuilder;
+///
+/// let metar = MetarBuilder::new("KSJC")
+///     .temp_c(15.0)
+///     .dewpoint_c(8.0)
+///     .wind(180, 10.0)
+///     .cloud("BKN", 800)
+///     .build();
+///
+/// assert_eq!(metar.temp_fahrenheit(), Some(59.0));
+/// assert_eq!(metar.ceiling_ft(), Some(800));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MetarBuilder {
+    metar: Metar,
+}
+
+impl MetarBuilder {
+    pub fn new(station_id: impl Into<String>) -> Self {
+        let metar = Metar {
+            station_id: station_id.into(),
+            ..Metar::default()
+        };
+
+        Self { metar }
+    }
+
+    pub fn raw_text(mut self, raw_text: impl Into<String>) -> Self {
+        let raw_text = raw_text.into();
+
+        self.metar.remarks = raw_text
+            .split_once(" RMK ")
+            .map(|(_, remarks)| String::from(remarks));
+        self.metar.raw_text = raw_text;
+        self
+    }
+
+    pub fn observation_time(mut self, time: chrono::DateTime<Utc>) -> Self {
+        self.metar.observation_time = Some(time);
+        self
+    }
+
+    pub fn position(mut self, lat: f64, lon: f64) -> Self {
+        self.metar.lat = Some(lat);
+        self.metar.lon = Some(lon);
+        self
+    }
+
+    pub fn temp_c(mut self, temp: f64) -> Self {
+        self.metar.temp_c = Temperature::Celsius(Some(temp));
+        self.metar.temp_f = Temperature::Fahrenheit(self.metar.temp_c.to_fahrenheit());
+        self
+    }
+
+    pub fn dewpoint_c(mut self, dewpoint: f64) -> Self {
+        self.metar.dewpoint_c = Temperature::Celsius(Some(dewpoint));
+        self.metar.dewpoint_f = Temperature::Fahrenheit(self.metar.dewpoint_c.to_fahrenheit());
+        self
+    }
+
+    pub fn wind(mut self, dir_degrees: i32, speed_kt: f64) -> Self {
+        self.metar.wind_dir_degrees = WindDirection::Degrees(Some(dir_degrees));
+        self.metar.wind_dir_cardinal = self.metar.wind_dir_degrees.to_cardinal_direction();
+        self.metar.wind_speed_kt = Wind::Knots(Some(speed_kt));
+        self.metar.wind_speed_mph = Wind::Mph(self.metar.wind_speed_kt.to_mph());
+        self
+    }
+
+    pub fn wind_gust_kt(mut self, gust_kt: f64) -> Self {
+        self.metar.wind_gust_kt = Wind::Knots(Some(gust_kt));
+        self.metar.wind_gust_mph = Wind::Mph(self.metar.wind_gust_kt.to_mph());
+        self
+    }
+
+    pub fn visibility_statute_mi(mut self, visibility: f64) -> Self {
+        self.metar.visibility_statute_mi = Visibility::StatuteMiles(Some(visibility), None);
+        self.metar.visibility_m =
+            Visibility::Meters(self.metar.visibility_statute_mi.to_meters(), None);
+        self
+    }
+
+    pub fn cloud(mut self, sky_cover: &str, base_ft_agl: i32) -> Self {
+        let mut cloud = Cloud {
+            sky_cover: Some(String::from(sky_cover)),
+            sky_cover_label: None,
+            cloud_base_ft_agl: Some(base_ft_agl),
+            convective_type: None,
+            convective_type_label: None,
+        };
+
+        cloud.sky_cover_label();
+
+        self.metar.clouds.push(cloud);
+        self
+    }
+
+    pub fn altim_in_hg(mut self, altim: f64) -> Self {
+        self.metar.altim_in_hg = Some(altim);
+        self
+    }
+
+    pub fn wx_string(mut self, wx_string: impl Into<String>) -> Self {
+        self.metar.wx_string = Some(wx_string.into());
+        self
+    }
+
+    pub fn flight_category(mut self, flight_category: impl Into<String>) -> Self {
+        self.metar.flight_category = Some(flight_category.into());
+        self
+    }
+
+    pub fn elevation_m(mut self, elevation: f64) -> Self {
+        self.metar.elevation_m = Elevation::Meters(Some(elevation));
+        self.metar.elevation_ft = Elevation::Feet(self.metar.elevation_m.to_feet());
+        self
+    }
+
+    pub fn remarks(mut self, remarks: impl Into<String>) -> Self {
+        self.metar.remarks = Some(remarks.into());
+        self
+    }
+
+    pub fn build(self) -> Metar {
+        self.metar
+    }
+}
+
+/// Controls where [`Metar::fetch_and_parse`] downloads from and which stations it keeps.
+///
+/// ```
+/// use metars::FetchConfig;
+///
+/// let config = FetchConfig::new()
+///     .prefixes(["C"])
+///     .timeout(std::time::Duration::from_secs(30));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FetchConfig {
+    url: String,
+    timeout: Option<std::time::Duration>,
+    retries: u32,
+    output_path: String,
+    prefixes: Vec<String>,
+    allowlist: Vec<String>,
+    blocklist: Vec<String>,
+    cache_dir: PathBuf,
+    cache_ttl: std::time::Duration,
+    force_refresh: bool,
+    keep_files: bool,
+    user_agent: String,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            url: String::from(METAR_CACHE_URL),
+            timeout: None,
+            retries: 0,
+            output_path: String::from("./metars.gz"),
+            prefixes: vec![String::from("K")],
+            allowlist: Vec::new(),
+            blocklist: Vec::new(),
+            cache_dir: env::temp_dir().join("metars"),
+            cache_ttl: std::time::Duration::from_secs(5 * 60),
+            force_refresh: false,
+            keep_files: false,
+            user_agent: String::from(DEFAULT_USER_AGENT),
+        }
+    }
+}
+
+impl FetchConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Points the fetch at a mirror or a local mock server instead of aviationweather.gov.
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Path of the downloaded archive; the CSV is extracted alongside it.
+    pub fn output_path(mut self, path: impl Into<String>) -> Self {
+        self.output_path = path.into();
+        self
+    }
+
+    pub fn prefixes<I, S>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// When non-empty, only these exact station IDs are kept, on top of the prefix filter.
+    pub fn allowlist<I, S>(mut self, stations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowlist = stations.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Stations always dropped, e.g. one with a known-bad sensor.
+    pub fn blocklist<I, S>(mut self, stations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.blocklist = stations.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn cache_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cache_dir = path.into();
+        self
+    }
+
+    /// Reuses a cached download younger than `ttl` instead of hitting the server again.
+    pub fn cache_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    pub fn force_refresh(mut self, force_refresh: bool) -> Self {
+        self.force_refresh = force_refresh;
+        self
+    }
+
+    /// Sent as the `User-Agent` header; the site asks operators to include a contact.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Leaves the extracted archive and CSV at `output_path` instead of deleting them
+    /// once parsed, e.g. to inspect a feed that fails to parse.
+    pub fn keep_files(mut self, keep_files: bool) -> Self {
+        self.keep_files = keep_files;
+        self
+    }
+
+    fn cache_path(&self) -> PathBuf {
+        let key: String = self
+            .url
+            .chars()
+            .map(|x| if x.is_ascii_alphanumeric() { x } else { '_' })
+            .collect();
+
+        self.cache_dir.join(key)
+    }
+
+    fn keeps_station(&self, station_id: &str) -> bool {
+        self.prefixes
+            .iter()
+            .any(|x| station_id.starts_with(x.as_str()))
+            && (self.allowlist.is_empty() || self.allowlist.iter().any(|x| x == station_id))
+            && !self.blocklist.iter().any(|x| x == station_id)
+    }
+
+    fn csv_path(&self) -> String {
+        Path::new(&self.output_path)
+            .with_extension("csv")
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metars {
+    pub reports: Vec<Metar>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParseStats {
+    pub total_rows: usize,
+    pub kept: usize,
+    pub skipped_prefix: usize,
+}
+
+/// Worst conditions across a set of reports; stations missing a value are left out of
+/// that aggregate.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SummaryStats {
+    pub flight_categories: BTreeMap<String, usize>,
+    pub lowest_ceiling: Option<(String, i32)>,
+    pub highest_wind: Option<(String, f64)>,
+    pub lowest_visibility_statute_mi: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StationDelta {
+    Added(String),
+    Removed(String),
+    Changed(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CeilingBand {
+    Low,
+    Medium,
+    High,
+    VeryHigh,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    Fresh,
+    Stale,
+}
+
+/// NATO colour states, ordered from best (BLU) to worst (RED).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorState {
+    Blu,
+    Wht,
+    Grn,
+    Ylo,
+    Amb,
+    Red,
+}
+
+/// Characteristic of the 3-hour pressure tendency, WMO code table 0200.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressureTrend {
+    RisingThenFalling,
+    RisingThenSteady,
+    Rising,
+    SteadyThenRising,
+    Steady,
+    FallingThenRising,
+    FallingThenSteady,
+    Falling,
+    SteadyThenFalling,
+}
+
+impl PressureTrend {
+    fn from_digit(digit: u8) -> Option<Self> {
+        let trend = match digit {
+            b'0' => Self::RisingThenFalling,
+            b'1' => Self::RisingThenSteady,
+            b'2' => Self::Rising,
+            b'3' => Self::SteadyThenRising,
+            b'4' => Self::Steady,
+            b'5' => Self::FallingThenRising,
+            b'6' => Self::FallingThenSteady,
+            b'7' => Self::Falling,
+            b'8' => Self::SteadyThenFalling,
+            _ => return None,
+        };
+
+        Some(trend)
+    }
+}
+
+impl Metar {
+    pub async fn fetch_metars() -> Result<(), MetarError> {
+        let url = METAR_CACHE_URL;
+
+        download_file(url, "./metars.gz", None, 0, DEFAULT_USER_AGENT).await
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn fetch_metars_blocking() -> Result<(), MetarError> {
+        let url = METAR_CACHE_URL;
+
+        download_file_blocking(url, "./metars.gz")
+    }
+
+    pub fn extract_metar_file(path: &str) -> Result<(), MetarError> {
+        extract_file(path, "./metars.csv", false)
+    }
+
+    pub async fn fetch_and_parse(config: &FetchConfig) -> Result<Metars, MetarError> {
+        let csv_path = config.csv_path();
+
+        let cache_path = config.cache_path();
+
+        if config.force_refresh || !is_fresh(&cache_path, config.cache_ttl, SystemTime::now()) {
+            fs::create_dir_all(&config.cache_dir)?;
+
+            download_file(
+                &config.url,
+                &cache_path.to_string_lossy(),
+                config.timeout,
+                config.retries,
+                &config.user_agent,
+            )
+            .await?;
+        } else {
+            info!("Using cached copy of {}", config.url);
+        }
+
+        fs::copy(&cache_path, &config.output_path)?;
+        extract_file(&config.output_path, &csv_path, config.keep_files)?;
+
+        let dataframe = Self::scan_metar_file(&csv_path, config.keep_files)?;
+        let mut metars: Vec<Self> = Vec::new();
+
+        Self::parse_rows(
+            &dataframe,
+            |station_id| config.keeps_station(station_id),
+            |metar| metars.push(metar),
+        )?;
+
+        Ok(Metars { reports: metars })
+    }
+
+    pub fn read_metar_file(path: &str) -> Result<DataFrame, MetarError> {
+        Self::scan_metar_file(path, false)
+    }
+
+    fn scan_metar_file(path: &str, keep_files: bool) -> Result<DataFrame, MetarError> {
+        strip_preamble(path)?;
+
+        let mut frame = LazyCsvReader::new(path)
+            .with_infer_schema_length(None)
+            .finish()?;
+
+        let schema = frame.collect_schema()?;
+        let columns = metar_projection(schema.iter_names().map(|x| x.as_str()))?;
+
+        let columns: Vec<Expr> = columns.iter().map(col).collect();
+        let dataframe = frame.select(columns).collect()?;
+
+        if !keep_files {
+            remove_if_exists(path)?;
+        }
+
+        Ok(dataframe)
+    }
+
+    /// Streams the response through the gzip decoder and reads it straight into a
+    /// DataFrame, skipping the archive and CSV files the other fetch functions write.
+    /// Polars still needs the whole CSV buffered to build the frame. Unlike
+    /// [`Metar::fetch_and_parse`], this path ignores the configured station filters and
+    /// never reads or writes the cache.
+    pub async fn fetch_metar_dataframe(config: &FetchConfig) -> Result<DataFrame, MetarError> {
+        let contents = fetch_decompressed(
+            &config.url,
+            config.timeout,
+            config.retries,
+            &config.user_agent,
+        )
+        .await?;
+
+        let contents = String::from_utf8(contents).map_err(|err| {
+            MetarError::Download(format!("response from {} is not UTF-8: {err}", config.url))
+        })?;
+
+        let offset = header_offset(&contents)?;
+
+        let dataframe = CsvReadOptions::default()
+            .with_infer_schema_length(None)
+            .into_reader_with_file_handle(Cursor::new(&contents.as_bytes()[offset..]))
+            .finish()?;
+
+        let columns = metar_projection(dataframe.get_column_names().iter().map(|x| x.as_str()))?;
+
+        Ok(dataframe.select(columns)?)
+    }
+
+    /// # Panics
+    ///
+    /// Panics when the DataFrame is missing a column the parser needs; see
+    /// [`Metar::try_parse_metars`] for the fallible version.
+    pub fn parse_metars(dataframe: &DataFrame) -> Metars {
+        Self::try_parse_metars(dataframe).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_parse_metars(dataframe: &DataFrame) -> Result<Metars, MetarError> {
+        let mut metars: Vec<Self> = Vec::new();
+
+        Self::try_parse_metars_with(dataframe, |metar| metars.push(metar))?;
+
+        Ok(Metars { reports: metars })
+    }
+
+    /// Collects the parsed stations along with counts of the rows kept and skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the DataFrame is missing a column the parser needs; see
+    /// [`Metar::try_parse_metars_with_stats`] for the fallible version.
+    pub fn parse_metars_with_stats(dataframe: &DataFrame) -> (Metars, ParseStats) {
+        Self::try_parse_metars_with_stats(dataframe).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_parse_metars_with_stats(
+        dataframe: &DataFrame,
+    ) -> Result<(Metars, ParseStats), MetarError> {
+        let mut metars: Vec<Self> = Vec::new();
+
+        let stats = Self::try_parse_metars_with(dataframe, |metar| metars.push(metar))?;
+
+        Ok((Metars { reports: metars }, stats))
+    }
+
+    /// Yields each station as its row is parsed, so a consumer can apply backpressure
+    /// instead of waiting on the whole feed. Parsing runs on a blocking thread; a schema
+    /// error ends the stream and is logged rather than yielded.
+    ///
+    /// It is `async` so the blocking thread is only spawned from code a runtime is already
+    /// driving, never from a plain synchronous call.
+    ///
+    /// # Panics
+    ///
+    /// Panics when awaited on an executor other than Tokio.
+    pub async fn parse_metars_stream(dataframe: &DataFrame) -> impl Stream<Item = Self> {
+        let dataframe = dataframe.clone();
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+
+        tokio::task::spawn_blocking(move || {
+            let result = Self::try_parse_metars_with(&dataframe, |metar| {
+                // A dropped receiver only means nobody wants the rest.
+                let _ = tx.blocking_send(metar);
+            });
+
+            if let Err(err) = result {
+                warn!("Stopped streaming metars: {err}");
+            }
+        });
+
+        stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|metar| (metar, rx))
+        })
+    }
+
+    /// Hands each station to `callback` as soon as its row is parsed.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the DataFrame is missing a column the parser needs; see
+    /// [`Metar::try_parse_metars_with`] for the fallible version.
+    pub fn parse_metars_with<F>(dataframe: &DataFrame, callback: F) -> ParseStats
+    where
+        F: FnMut(Self),
+    {
+        Self::try_parse_metars_with(dataframe, callback).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_parse_metars_with<F>(
+        dataframe: &DataFrame,
+        callback: F,
+    ) -> Result<ParseStats, MetarError>
+    where
+        F: FnMut(Self),
+    {
+        Self::parse_rows(
+            dataframe,
+            |station_id| station_id.starts_with('K'),
+            callback,
+        )
+    }
+
+    fn parse_rows<K, F>(
+        dataframe: &DataFrame,
+        keep_station: K,
+        mut callback: F,
+    ) -> Result<ParseStats, MetarError>
+    where
+        K: Fn(&str) -> bool,
+        F: FnMut(Self),
+    {
+        let column = |name: &str| {
+            dataframe
+                .get_column_index(name)
+                .ok_or_else(|| MetarError::Schema(format!("missing column `{name}`")))
+        };
+
+        let raw_text_column = column("raw_text")?;
+        let station_id_column = column("station_id")?;
+        let observation_time_column = column("observation_time")?;
+        let latitude_column = column("latitude")?;
+        let longitude_column = column("longitude")?;
+        let temp_c_column = column("temp_c")?;
+        let dewpoint_c_column = column("dewpoint_c")?;
+        let wind_dir_degrees_column = column("wind_dir_degrees")?;
+        let wind_speed_kt_column = column("wind_speed_kt")?;
+        let wind_gust_kt_column = column("wind_gust_kt")?;
+        let visibility_statute_mi_column = column("visibility_statute_mi")?;
+        let altim_in_hg_column = column("altim_in_hg")?;
+        let wx_string_column = column("wx_string")?;
+        let flight_category_column = column("flight_category")?;
+        let metar_type_column = column("metar_type")?;
+        let vert_vis_ft_column = column("vert_vis_ft")?;
+        let elevation_m_column = column("elevation_m")?;
+        // Older captures predate this column, so it is optional.
+        let maintenance_indicator_column = dataframe.get_column_index("maintenance_indicator_on");
+
+        let mut stats = ParseStats {
+            total_rows: dataframe.height(),
+            ..ParseStats::default()
+        };
+
+        // Each `sky_cover_duplicated_N` pairs with `cloud_base_ft_agl_duplicated_N`
+        // wherever the feed happens to place it.
+        let cloud_columns = dataframe
+            .get_column_names()
+            .iter()
+            .filter_map(|name| name.strip_prefix("sky_cover"))
+            .map(|suffix| {
+                let sky_cover_column = column(&format!("sky_cover{suffix}"))?;
+                let cloud_base_column = column(&format!("cloud_base_ft_agl{suffix}"))?;
+
+                Ok((sky_cover_column, cloud_base_column))
+            })
+            .collect::<Result<Vec<(usize, usize)>, MetarError>>()?;
+
+        for i in 0..dataframe.height() {
+            if let Some(row) = dataframe.get(i) {
+                let station_id = row[station_id_column].str_value();
+
+                if keep_station(&station_id) {
+                    let station_id = station_id.into_owned();
+                    let raw_text = row[raw_text_column].str_value().into_owned();
+
+                    let observation_time: Option<chrono::DateTime<Utc>> =
+                        if row[observation_time_column].is_null() {
+                            parse_time_group(&raw_text, Utc::now())
+                        } else {
+                            match row[observation_time_column].str_value().parse() {
+                                Ok(val) => Some(val),
+                                Err(_) => None,
+                            }
+                        };
+
+                    // Out-of-range coordinates would poison bbox and nearest-station queries.
+                    let lat =
+                        parse_column(&station_id, "latitude", &row[latitude_column], value_f64)
+                            .filter(|val| (-90.0..=90.0).contains(val));
+                    let lon =
+                        parse_column(&station_id, "longitude", &row[longitude_column], value_f64)
+                            .filter(|val| (-180.0..=180.0).contains(val));
+
+                    let (raw_temp, raw_dewpoint) =
+                        parse_temperature_dewpoint_group(&raw_text).unwrap_or((None, None));
+
+                    let (precise_temp, precise_dewpoint) =
+                        parse_precise_temperature_group(&raw_text).unwrap_or((None, None));
+
+                    let temp_c = if precise_temp.is_some() {
+                        Temperature::Celsius(precise_temp)
+                    } else if row[temp_c_column].is_null() {
+                        Temperature::Celsius(raw_temp)
+                    } else {
+                        Temperature::Celsius(parse_column(
+                            &station_id,
+                            "temp_c",
+                            &row[temp_c_column],
+                            value_temperature,
+                        ))
+                    };
+
+                    let temp_f = Temperature::Fahrenheit(temp_c.to_fahrenheit());
+
+                    let dewpoint_c = if precise_dewpoint.is_some() {
+                        Temperature::Celsius(precise_dewpoint)
+                    } else if row[dewpoint_c_column].is_null() {
+                        Temperature::Celsius(raw_dewpoint)
+                    } else {
+                        Temperature::Celsius(parse_column(
+                            &station_id,
+                            "dewpoint_c",
+                            &row[dewpoint_c_column],
+                            value_temperature,
+                        ))
+                    };
+
+                    let dewpoint_f = Temperature::Fahrenheit(dewpoint_c.to_fahrenheit());
+
+                    let wind_dir_degrees =
+                        if value_str(&row[wind_dir_degrees_column]) == Some("VRB") {
+                            WindDirection::Variable(Some(String::from("VRB")))
+                        } else {
+                            WindDirection::Degrees(parse_column(
+                                &station_id,
+                                "wind_dir",
+                                &row[wind_dir_degrees_column],
+                                value_i32,
+                            ))
+                        };
+
+                    let wind_dir_cardinal = wind_dir_degrees.to_cardinal_direction();
+
+                    let wind_speed_kt = Wind::Knots(parse_column(
+                        &station_id,
+                        "wind_speed",
+                        &row[wind_speed_kt_column],
+                        value_f64,
+                    ));
+
+                    let wind_speed_mph = Wind::Mph(wind_speed_kt.to_mph());
+
+                    let wind_gust_kt = Wind::Knots(parse_column(
+                        &station_id,
+                        "wind_gust",
+                        &row[wind_gust_kt_column],
+                        value_f64,
+                    ));
+
+                    let wind_gust_mph = Wind::Mph(wind_gust_kt.to_mph());
+
+                    let cavok = has_cavok_group(&raw_text);
+
+                    let raw_visibility = if cavok {
+                        Visibility::parse_group("9999")
+                    } else {
+                        Visibility::parse_raw_group(&raw_text)
+                    };
+
+                    let raw_qualifier = raw_visibility.as_ref().and_then(Visibility::qualifier);
+
+                    let visibility_statute_mi = if row[visibility_statute_mi_column].is_null() {
+                        Visibility::StatuteMiles(
+                            raw_visibility
+                                .as_ref()
+                                .and_then(Visibility::to_statute_miles),
+                            raw_qualifier,
+                        )
+                    } else {
+                        let visibility = parse_column(
+                            &station_id,
+                            "visibility",
+                            &row[visibility_statute_mi_column],
+                            |val| match value_str(val) {
+                                Some(x) => match parse_visibility_value(x) {
+                                    (Some(statute_mi), qualifier) => Some((statute_mi, qualifier)),
+                                    (None, _) => None,
+                                },
+                                None => val.extract::<f64>().map(|x| (x, None)),
+                            },
+                        );
+
+                        match visibility {
+                            Some((statute_mi, qualifier)) => Visibility::StatuteMiles(
+                                Some(statute_mi),
+                                qualifier.or(raw_qualifier),
+                            ),
+                            None => Visibility::StatuteMiles(None, raw_qualifier),
+                        }
+                    };
+
+                    let visibility_m =
+                        Visibility::meters_from(raw_visibility.as_ref(), &visibility_statute_mi);
+
+                    let altim_in_hg =
+                        parse_column(&station_id, "altim", &row[altim_in_hg_column], value_f64);
+
+                    let mut clouds = Vec::new();
+
+                    for &(sky_cover_column, cloud_base_column) in &cloud_columns {
+                        let sky_cover = if row[sky_cover_column].is_null() {
+                            None
+                        } else {
+                            Some(row[sky_cover_column].str_value().into_owned())
+                        };
+
+                        let cloud_base = parse_column(
+                            &station_id,
+                            "cloud_base",
+                            &row[cloud_base_column],
+                            value_i32,
+                        );
+
+                        if sky_cover.is_none() && cloud_base.is_none() {
+                            continue;
+                        };
+
+                        let mut cloud = Cloud {
+                            sky_cover,
+                            cloud_base_ft_agl: cloud_base,
+                            sky_cover_label: None,
+                            convective_type: None,
+                            convective_type_label: None,
+                        };
+
+                        cloud.sky_cover_label();
+
+                        clouds.push(cloud);
+                    }
+
+                    let raw_clouds = Cloud::parse_raw_layers(&raw_text);
+
+                    if raw_clouds.len() > clouds.len() {
+                        clouds = raw_clouds;
+                    } else {
+                        for cloud in &mut clouds {
+                            let raw_cloud = raw_clouds.iter().find(|x| {
+                                x.sky_cover == cloud.sky_cover
+                                    && x.cloud_base_ft_agl == cloud.cloud_base_ft_agl
+                            });
+
+                            if let Some(val) = raw_cloud {
+                                cloud.convective_type.clone_from(&val.convective_type);
+                                cloud.convective_type_label();
+                            }
+                        }
+                    }
+
+                    let vert_vis_ft =
+                        parse_column(&station_id, "vert_vis", &row[vert_vis_ft_column], value_i32);
+
+                    // An obscured sky is reported as OVX at the surface; the vertical
+                    // visibility is what actually limits the ceiling.
+                    if let Some(val) = vert_vis_ft {
+                        clouds.retain(|cloud| {
+                            !matches!(cloud.sky_cover.as_deref(), Some("OVX" | "VV"))
+                        });
+
+                        let mut cloud = Cloud {
+                            sky_cover: Some(String::from("VV")),
+                            cloud_base_ft_agl: Some(val),
+                            sky_cover_label: None,
+                            convective_type: None,
+                            convective_type_label: None,
+                        };
+
+                        cloud.sky_cover_label();
+
+                        clouds.push(cloud);
+                    }
+
+                    clouds.sort_by_key(|cloud| cloud.cloud_base_ft_agl);
+
+                    if cavok {
+                        clouds.clear();
+                    }
+
+                    let wx_string = if cavok || row[wx_string_column].is_null() {
+                        None
+                    } else {
+                        Some(row[wx_string_column].str_value().into_owned())
+                    };
+
+                    let flight_category = if row[flight_category_column].is_null() {
+                        None
+                    } else {
+                        Some(row[flight_category_column].str_value().into_owned())
+                    };
+
+                    let report_type = if row[metar_type_column].is_null() {
+                        None
+                    } else {
+                        Some(ReportType::from(
+                            row[metar_type_column].str_value().as_ref(),
+                        ))
+                    };
+
+                    let elevation_m = Elevation::Meters(
+                        parse_column(
+                            &station_id,
+                            "elevation",
+                            &row[elevation_m_column],
+                            value_f64,
+                        )
+                        .filter(|&val| val != MISSING_ELEVATION_M),
+                    );
+
+                    let elevation_ft = Elevation::Feet(elevation_m.to_feet());
+
+                    let remarks = if row[raw_text_column].is_null() {
+                        None
+                    } else {
+                        let remarks = row[raw_text_column].str_value();
+
+                        if remarks.contains("RMK") {
+                            let remarks: Vec<&str> = remarks.split(' ').collect();
+                            let index = remarks.iter().position(|&x| x == "RMK");
+
+                            match index {
+                                Some(val) => Some(remarks[val + 1..].join(" ")),
+                                None => None,
+                            }
+                        } else {
+                            None
+                        }
+                    };
+
+                    let rvr = RunwayVisualRange::parse_raw_groups(&raw_text);
+                    let wind_shear = WindShear::parse_raw_groups(&raw_text);
+                    let recent_weather = parse_recent_weather(&raw_text);
+
+                    let maintenance_indicator =
+                        maintenance_indicator_column.and_then(|x| match &row[x] {
+                            AnyValue::Null => None,
+                            AnyValue::Boolean(val) => Some(*val),
+                            val => Some(val.str_value().eq_ignore_ascii_case("true")),
+                        });
+
+                    let metar = Self {
+                        raw_text,
+                        station_id,
+                        observation_time,
+                        lat,
+                        lon,
+                        temp_c,
+                        temp_f,
+                        dewpoint_c,
+                        dewpoint_f,
+                        wind_dir_degrees,
+                        wind_dir_cardinal,
+                        wind_speed_kt,
+                        wind_speed_mph,
+                        wind_gust_kt,
+                        wind_gust_mph,
+                        visibility_statute_mi,
+                        visibility_m,
+                        clouds,
+                        rvr,
+                        wind_shear,
+                        recent_weather,
+                        altim_in_hg,
+                        wx_string,
+                        flight_category,
+                        report_type,
+                        elevation_m,
+                        elevation_ft,
+                        remarks,
+                        maintenance_indicator,
+                    };
+
+                    debug!("Parsed {}: {metar}", metar.station_id);
+
+                    callback(metar);
+                    stats.kept += 1;
+                } else {
+                    stats.skipped_prefix += 1;
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Flat accessors for callers who only want the number out of each quantity.
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), metars::MetarError> {
+    /// use metars::{FetchConfig, Metar};
+    ///
+    /// let metars = Metar::fetch_and_parse(&FetchConfig::new()).await?;
+    ///
+    /// for metar in &metars {
+    ///     let temp = metar.temp_celsius().unwrap_or_default();
+    ///     let wind = metar.wind_knots().unwrap_or_default();
+    ///     let gust = metar.wind_gust_knots();
+    ///
+    ///     println!("{} {temp}°C {wind}kt {gust:?}", metar.station_id);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn temp_celsius(&self) -> Option<f64> {
+        self.temp_c.to_celsius()
+    }
+
+    pub fn temp_fahrenheit(&self) -> Option<f64> {
+        self.temp_f.to_fahrenheit()
+    }
+
+    pub fn elevation_meters(&self) -> Option<f64> {
+        self.elevation_m.to_meters()
+    }
+
+    pub fn elevation_feet(&self) -> Option<f64> {
+        self.elevation_ft.to_feet()
+    }
+
+    pub fn wind_knots(&self) -> Option<f64> {
+        self.wind_speed_kt.to_knots()
+    }
+
+    pub fn wind_mph(&self) -> Option<f64> {
+        self.wind_speed_mph.to_mph()
+    }
+
+    pub fn wind_gust_knots(&self) -> Option<f64> {
+        self.wind_gust_kt.to_knots()
+    }
+
+    // Points toward the direction the wind is blowing from, like the shaft of a wind
+    // barb, so a northerly wind is drawn as '↑' rather than the downwind '↓'.
+    pub fn wind_source_arrow(&self) -> Option<char> {
+        match self.wind_dir_degrees {
+            WindDirection::Degrees(Some(val)) => {
+                let arrows: [char; 8] = ['↑', '↗', '→', '↘', '↓', '↙', '←', '↖'];
+                let index = (f64::from(val.rem_euclid(360)) / 45.0).round() as usize % 8;
+
+                Some(arrows[index])
+            }
+            _ => None,
+        }
+    }
+
+    pub fn relative_humidity(&self) -> Option<f64> {
+        let temp = self.temp_c.to_celsius()?;
+        let dewpoint = self.dewpoint_c.to_celsius()?;
+
+        let saturation = |val: f64| (17.625 * val / (243.04 + val)).exp();
+
+        finite(100.0 * saturation(dewpoint) / saturation(temp))
+    }
+
+    pub fn heat_index_f(&self) -> Option<f64> {
+        let temp = self.temp_f.to_fahrenheit()?;
+        let rh = self.relative_humidity()?;
+
+        if temp < 80.0 {
+            return None;
+        }
+
+        let heat_index = -42.379 + 2.049_015_23 * temp + 10.143_331_27 * rh
+            - 0.224_755_41 * temp * rh
+            - 0.006_837_83 * temp * temp
+            - 0.054_817_17 * rh * rh
+            + 0.001_228_74 * temp * temp * rh
+            + 0.000_852_82 * temp * rh * rh
+            - 0.000_001_99 * temp * temp * rh * rh;
+
+        finite(heat_index)
+    }
+
+    pub fn wind_chill_f(&self) -> Option<f64> {
+        let temp = self.temp_f.to_fahrenheit()?;
+        let wind = self.wind_speed_mph.to_mph()?;
+
+        if temp > 50.0 || wind < 3.0 {
+            return None;
+        }
+
+        let factor = wind.powf(0.16);
+        let wind_chill = 35.74 + 0.6215 * temp - 35.75 * factor + 0.4275 * temp * factor;
+
+        finite(wind_chill)
+    }
+
+    pub fn has_gust(&self) -> bool {
+        self.gust_factor().is_some()
+    }
+
+    /// Gust spread over the steady wind in knots; gusts at or below it are ignored.
+    pub fn gust_factor(&self) -> Option<f64> {
+        let gust = self.wind_gust_knots()?;
+        let speed = self.wind_knots().unwrap_or(0.0);
+
+        (gust > speed).then_some(gust - speed)
+    }
+
+    pub fn dewpoint_spread_c(&self) -> Option<f64> {
+        let temp = self.temp_c.to_celsius()?;
+        let dewpoint = self.dewpoint_c.to_celsius()?;
+
+        finite(temp - dewpoint)
+    }
+
+    pub fn dewpoint_spread_f(&self) -> Option<f64> {
+        let temp = self.temp_f.to_fahrenheit()?;
+        let dewpoint = self.dewpoint_f.to_fahrenheit()?;
+
+        finite(temp - dewpoint)
+    }
+
+    /// A spread of 3°C or less often precedes fog.
+    pub fn fog_risk(&self) -> bool {
+        self.dewpoint_spread_c().is_some_and(|val| val <= 3.0)
+    }
+
+    /// Stull's (2011) empirical fit, valid near sea-level pressure for 5–99% humidity.
+    pub fn wet_bulb_c(&self) -> Option<f64> {
+        let temp = self.temp_c.to_celsius()?;
+        let rh = self.relative_humidity()?;
+
+        let wet_bulb = temp * (0.151_977 * (rh + 8.313_659).sqrt()).atan() + (temp + rh).atan()
+            - (rh - 1.676_331).atan()
+            + 0.003_918_38 * rh.powf(1.5) * (0.023_101 * rh).atan()
+            - 4.686_035;
+
+        finite(wet_bulb)
+    }
+
+    pub fn bearing_to(&self, other: &Metar) -> Option<f64> {
+        let from = (self.lat?, self.lon?);
+        let to = (other.lat?, other.lon?);
+
+        bearing_degrees(from, to)
+    }
+
+    pub fn bearing_to_cardinal(&self, other: &Metar) -> Option<String> {
+        self.bearing_to(other)
+            .map(|val| String::from(cardinal_direction(val)))
+    }
+
+    /// Compares what a pilot would notice, ignoring `observation_time` and `raw_text`,
+    /// so a report whose only change is the timestamp counts as unchanged.
+    pub fn same_conditions(&self, other: &Metar) -> bool {
+        self.wind_dir_degrees == other.wind_dir_degrees
+            && self.wind_knots() == other.wind_knots()
+            && self.wind_gust_knots() == other.wind_gust_knots()
+            && self.visibility_statute_mi == other.visibility_statute_mi
+            && self.clouds == other.clouds
+            && self.temp_c == other.temp_c
+            && self.dewpoint_c == other.dewpoint_c
+            && self.altim_in_hg == other.altim_in_hg
+            && self.wx_string == other.wx_string
+    }
+
+    /// `00000KT`; unlike a missing wind group, this is a reported zero speed.
+    pub fn is_calm(&self) -> bool {
+        self.wind_knots().is_some_and(|val| val == 0.0)
+    }
+
+    pub fn wind_summary(&self, show_mph: bool) -> Option<String> {
+        let speed = self.wind_knots()?;
+
+        if self.is_calm() {
+            return Some(String::from("Calm"));
+        }
+
+        let format_speed = |knots: f64, mph: Option<f64>| match mph {
+            Some(val) if show_mph => format!("{knots:.0} kt ({val:.0} mph)"),
+            _ => format!("{knots:.0} kt"),
+        };
+
+        let direction = match &self.wind_dir_degrees {
+            WindDirection::Degrees(Some(val)) => format!("{val:03}"),
+            WindDirection::Variable(_) => String::from("Variable"),
+            WindDirection::Degrees(None) => String::from("--"),
+        };
+
+        let mut summary = format!(
+            "{direction} at {}",
+            format_speed(speed, self.wind_speed_mph.to_mph())
+        );
+
+        if let Some(gust) = self.wind_gust_knots() {
+            summary.push_str(&format!(
+                " gusting {}",
+                format_speed(gust, self.wind_gust_mph.to_mph())
+            ));
+        }
+
+        Some(summary)
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+
+        if let Some(speed) = self.wind_knots() {
+            if self.is_calm() {
+                parts.push(String::from("winds calm"));
+            } else {
+                let direction = match (&self.wind_dir_degrees, &self.wind_dir_cardinal) {
+                    (WindDirection::Variable(_), _) => String::from("variable"),
+                    (_, Some(val)) => format!("from the {}", cardinal_direction_name(val)),
+                    _ => String::from("from an unknown direction"),
+                };
+
+                let mut wind = format!("winds {direction} at {speed:.0} knots");
+
+                if let Some(gust) = self.wind_gust_knots() {
+                    wind.push_str(&format!(" gusting {gust:.0}"));
+                }
+
+                parts.push(wind);
+            }
+        }
+
+        if let Some(val) = self.visibility_statute_mi.to_statute_miles() {
+            let unit = if val == 1.0 { "mile" } else { "miles" };
+
+            parts.push(format!("visibility {val} {unit}"));
+        }
+
+        if let Some(val) = &self.wx_string {
+            let weather: Vec<String> = val
+                .split_whitespace()
+                .filter_map(decode_weather_group)
+                .collect();
+
+            if !weather.is_empty() {
+                parts.push(weather.join(" and "));
+            }
+        }
+
+        for cloud in &self.clouds {
+            let Some(label) = &cloud.sky_cover_label else {
+                continue;
+            };
+
+            let layer = match (cloud.sky_cover.as_deref(), cloud.cloud_base_ft_agl) {
+                (Some("CLR" | "SKC"), _) => String::from("clear skies"),
+                (Some("OVC"), Some(base)) => format!("overcast at {} feet", format_thousands(base)),
+                (_, Some(base)) => format!(
+                    "{} clouds at {} feet",
+                    label.to_lowercase(),
+                    format_thousands(base)
+                ),
+                _ => continue,
+            };
+
+            parts.push(layer);
+        }
+
+        if let Some(val) = self.temp_c.to_celsius() {
+            parts.push(format!("temperature {val:.0}°C"));
+        }
+
+        let mut summary = parts.join(", ");
+
+        if let Some(first) = summary.get(..1) {
+            summary.replace_range(..1, &first.to_uppercase());
+        }
+
+        summary.push('.');
+
+        summary
+    }
+
+    // Positive headwind is a headwind and negative a tailwind; positive crosswind
+    // blows from the right of the runway and negative from the left.
+    pub fn wind_components(&self, runway_heading_deg: i32) -> Option<(f64, f64)> {
+        let WindDirection::Degrees(Some(direction)) = self.wind_dir_degrees else {
+            return None;
+        };
+
+        let speed = self.wind_knots()?;
+
+        let angle = f64::from(direction - runway_heading_deg).to_radians();
+
+        Some((finite(speed * angle.cos())?, finite(speed * angle.sin())?))
+    }
+
+    /// Decodes the body groups of a raw report without any of the feed's columns.
+    pub fn from_raw_text(raw_text: &str) -> Self {
+        let groups: Vec<&str> = raw_text
+            .split_whitespace()
+            .take_while(|&x| x != "RMK")
+            .collect();
+
+        let report_type = groups
+            .first()
+            .filter(|&&x| x == "METAR" || x == "SPECI")
+            .map(|&x| ReportType::from(x));
+
+        let station_id = groups
+            .get(usize::from(report_type.is_some()))
+            .map_or_else(String::new, |&x| String::from(x));
+
+        let (wind_dir_degrees, wind_speed_kt, wind_gust_kt) =
+            groups.iter().find_map(|x| parse_wind_group(x)).unwrap_or((
+                WindDirection::Degrees(None),
+                Wind::Knots(None),
+                Wind::Knots(None),
+            ));
+
+        let cavok = has_cavok_group(raw_text);
+
+        let raw_visibility = if cavok {
+            Visibility::parse_group("9999")
+        } else {
+            Visibility::parse_raw_group(raw_text)
+        };
+
+        let visibility_statute_mi = Visibility::StatuteMiles(
+            raw_visibility
+                .as_ref()
+                .and_then(Visibility::to_statute_miles),
+            raw_visibility.as_ref().and_then(Visibility::qualifier),
+        );
+
+        let visibility_m = Visibility::meters_from(raw_visibility.as_ref(), &visibility_statute_mi);
+
+        let clouds = if cavok {
+            Vec::new()
+        } else {
+            Cloud::parse_raw_layers(raw_text)
+        };
+
+        let (raw_temp, raw_dewpoint) =
+            parse_temperature_dewpoint_group(raw_text).unwrap_or((None, None));
+
+        let (precise_temp, precise_dewpoint) =
+            parse_precise_temperature_group(raw_text).unwrap_or((None, None));
+
+        let temp_c = Temperature::Celsius(precise_temp.or(raw_temp));
+        let dewpoint_c = Temperature::Celsius(precise_dewpoint.or(raw_dewpoint));
+
+        let altim_in_hg = groups.iter().find_map(|x| {
+            let digits = x
+                .get(1..)
+                .filter(|val| val.len() == 4 && val.bytes().all(|x| x.is_ascii_digit()))?;
+
+            let val = digits.parse::<f64>().ok()?;
+
+            match &x[..1] {
+                "A" => Some(val / 100.0),
+                "Q" => Some((val * 0.029_53 * 100.0).round() / 100.0),
+                _ => None,
+            }
+        });
+
+        let weather: Vec<&str> = groups
+            .iter()
+            .copied()
+            .filter(|x| is_weather_group(x))
+            .collect();
+
+        let wx_string = (!cavok && !weather.is_empty()).then(|| weather.join(" "));
+
+        Self {
+            raw_text: String::from(raw_text),
+            station_id,
+            observation_time: parse_time_group(raw_text, Utc::now()),
+            temp_f: Temperature::Fahrenheit(temp_c.to_fahrenheit()),
+            temp_c,
+            dewpoint_f: Temperature::Fahrenheit(dewpoint_c.to_fahrenheit()),
+            dewpoint_c,
+            wind_dir_cardinal: wind_dir_degrees.to_cardinal_direction(),
+            wind_dir_degrees,
+            wind_speed_mph: Wind::Mph(wind_speed_kt.to_mph()),
+            wind_speed_kt,
+            wind_gust_mph: Wind::Mph(wind_gust_kt.to_mph()),
+            wind_gust_kt,
+            visibility_statute_mi,
+            visibility_m,
+            clouds,
+            rvr: RunwayVisualRange::parse_raw_groups(raw_text),
+            wind_shear: WindShear::parse_raw_groups(raw_text),
+            recent_weather: parse_recent_weather(raw_text),
+            altim_in_hg,
+            wx_string,
+            report_type,
+            remarks: raw_text
+                .split_once(" RMK ")
+                .map(|(_, remarks)| String::from(remarks)),
+            ..Self::default()
+        }
+    }
+
+    /// Rebuilds a raw report from the parsed fields. The result is not byte-identical
+    /// to `raw_text`, but decodes back to the same conditions with `from_raw_text`.
+    pub fn to_raw_string(&self) -> String {
+        let mut groups = Vec::new();
+
+        if let Some(val) = &self.report_type {
+            groups.push(String::from(val.as_str()));
+        }
+
+        groups.push(self.station_id.clone());
+
+        if let Some(val) = self.observation_time {
+            groups.push(val.format("%d%H%MZ").to_string());
+        }
+
+        if let Some((speed, unit)) = self.wind_speed_kt.group_value() {
+            let direction = match &self.wind_dir_degrees {
+                WindDirection::Degrees(Some(val)) => format!("{val:03}"),
+                _ => String::from("VRB"),
+            };
+
+            let gust = self
+                .wind_gust_kt
+                .group_value()
+                .filter(|&(_, x)| x == unit)
+                .map_or_else(String::new, |(val, _)| format!("G{val:02.0}"));
+
+            groups.push(format!("{direction}{speed:02.0}{gust}{unit}"));
+        }
+
+        if self.is_cavok() {
+            groups.push(String::from("CAVOK"));
+        } else if let Some(val) = self.visibility_statute_mi.to_statute_miles() {
+            let prefix = match self.visibility_statute_mi.qualifier() {
+                Some(VisibilityQualifier::GreaterThan) => "P",
+                Some(VisibilityQualifier::LessThan) => "M",
+                None => "",
+            };
+
+            match format_statute_miles(val) {
+                Some(miles) => groups.push(format!("{prefix}{miles}SM")),
+                None => groups.push(format_meters(
+                    self.visibility_m.to_meters().unwrap_or(val * 1609.344),
+                )),
+            }
+        }
+
+        groups.extend(self.wx_string.iter().cloned());
+        groups.extend(self.clouds.iter().filter_map(Cloud::to_group));
+
+        if let Some(temp) = self.temp_c.to_celsius() {
+            let dewpoint = self
+                .dewpoint_c
+                .to_celsius()
+                .map_or_else(String::new, format_temperature_group);
+
+            groups.push(format!("{}/{dewpoint}", format_temperature_group(temp)));
+        }
+
+        if let Some(val) = self.altim_in_hg {
+            groups.push(format!("A{:04.0}", val * 100.0));
+        }
+
+        if let Some(val) = &self.remarks {
+            groups.push(format!("RMK {val}"));
+        }
+
+        groups.join(" ")
+    }
+
+    pub fn to_string_map(&self) -> BTreeMap<String, Option<String>> {
+        let to_string = |val: Option<f64>| val.map(|x| x.to_string());
+
+        let knots = |wind: &Wind| match *wind {
+            Wind::Mph(_) => None,
+            _ => wind.to_knots(),
+        };
+
+        let wind_dir_degrees = match &self.wind_dir_degrees {
+            WindDirection::Degrees(val) => val.map(|x| x.to_string()),
+            WindDirection::Variable(val) => val.clone(),
+        };
+
+        let elevation_m = match self.elevation_m {
+            Elevation::Meters(val) => val,
+            Elevation::Feet(_) => None,
+        };
+
+        let clouds: Vec<String> = self.clouds.iter().filter_map(Cloud::to_group).collect();
+
+        let fields = [
+            ("raw_text", Some(self.raw_text.clone())),
+            ("station_id", Some(self.station_id.clone())),
+            (
+                "observation_time",
+                self.observation_time.map(|x| x.to_rfc3339()),
+            ),
+            ("lat", to_string(self.lat)),
+            ("lon", to_string(self.lon)),
+            ("temp_c", to_string(self.temp_c.to_celsius())),
+            ("temp_f", to_string(self.temp_f.to_fahrenheit())),
+            ("dewpoint_c", to_string(self.dewpoint_c.to_celsius())),
+            ("dewpoint_f", to_string(self.dewpoint_f.to_fahrenheit())),
+            ("wind_dir_degrees", wind_dir_degrees),
+            ("wind_dir_cardinal", self.wind_dir_cardinal.clone()),
+            ("wind_speed_kt", to_string(knots(&self.wind_speed_kt))),
+            ("wind_speed_mph", to_string(self.wind_speed_mph.to_mph())),
+            ("wind_gust_kt", to_string(knots(&self.wind_gust_kt))),
+            ("wind_gust_mph", to_string(self.wind_gust_mph.to_mph())),
+            (
+                "visibility_statute_mi",
+                to_string(self.visibility_statute_mi.to_statute_miles()),
+            ),
+            (
+                "clouds",
+                if clouds.is_empty() {
+                    None
+                } else {
+                    Some(clouds.join(" "))
+                },
+            ),
+            ("altim_in_hg", to_string(self.altim_in_hg)),
+            ("wx_string", self.wx_string.clone()),
+            ("flight_category", self.flight_category.clone()),
+            (
+                "report_type",
+                self.report_type.as_ref().map(ToString::to_string),
+            ),
+            ("elevation_m", to_string(elevation_m)),
+            ("elevation_ft", to_string(self.elevation_ft.to_feet())),
+            ("remarks", self.remarks.clone()),
+        ];
+
+        fields
+            .into_iter()
+            .map(|(key, val)| (String::from(key), val))
+            .collect()
+    }
+
+    pub fn to_avwx_json(&self) -> serde_json::Value {
+        let number = |repr: Option<String>, value: Option<f64>| match repr {
+            Some(repr) => json!({ "repr": repr, "value": value }),
+            None => serde_json::Value::Null,
+        };
+
+        let knots = |wind: &Wind| match *wind {
+            Wind::Mph(_) => None,
+            _ => wind.to_knots(),
+        };
+
+        let wind_direction = match &self.wind_dir_degrees {
+            WindDirection::Degrees(val) => {
+                number(val.map(|x| format!("{x:03}")), val.map(f64::from))
+            }
+            WindDirection::Variable(val) => number(val.clone(), None),
+        };
+
+        let wind_speed = knots(&self.wind_speed_kt);
+        let wind_gust = knots(&self.wind_gust_kt);
+        let temperature = self.temp_c.to_celsius();
+        let dewpoint = self.dewpoint_c.to_celsius();
+        let visibility = self.visibility_statute_mi.to_statute_miles();
+
+        let clouds: Vec<serde_json::Value> = self
+            .clouds
+            .iter()
+            .map(|cloud| {
+                json!({
+                    "repr": cloud.to_group(),
+                    "type": cloud.sky_cover,
+                    "altitude": cloud.cloud_base_ft_agl.map(|x| x / 100),
+                    "modifier": cloud.convective_type,
+                })
+            })
+            .collect();
+
+        let wx_codes: Vec<&str> = self
+            .wx_string
+            .as_deref()
+            .map(|val| val.split_whitespace().collect())
+            .unwrap_or_default();
+
+        json!({
+            "raw": self.raw_text,
+            "sanitized": self.raw_text.split_whitespace().collect::<Vec<_>>().join(" "),
+            "station": self.station_id,
+            "time": self.observation_time.map(|x| json!({
+                "repr": x.format("%d%H%MZ").to_string(),
+                "dt": x.to_rfc3339(),
+            })),
+            "flight_rules": self.flight_category,
+            "wind_direction": wind_direction,
+            "wind_speed": number(wind_speed.map(|x| format!("{x:02}")), wind_speed),
+            "wind_gust": number(wind_gust.map(|x| format!("{x:02}")), wind_gust),
+            "visibility": number(visibility.map(|x| x.to_string()), visibility),
+            "temperature": number(temperature.map(|x| x.to_string()), temperature),
+            "dewpoint": number(dewpoint.map(|x| x.to_string()), dewpoint),
+            "altimeter": number(self.altim_in_hg.map(|x| format!("{x:.2}")), self.altim_in_hg),
+            "clouds": clouds,
+            "wx_codes": wx_codes,
+            "remarks": self.remarks,
+            "units": {
+                "altimeter": "inHg",
+                "altitude": "ft",
+                "temperature": "C",
+                "visibility": "sm",
+                "wind_speed": "kt",
+            },
+        })
+    }
+
+    pub fn faa_id(&self) -> Option<&str> {
+        match self.station_id.strip_prefix('K') {
+            Some(val) if self.station_id.len() == 4 => Some(val),
+            _ => None,
+        }
+    }
+
+    pub fn station_id_consistent(&self) -> bool {
+        self.raw_text
+            .split_whitespace()
+            .any(|x| x == self.station_id || Some(x) == self.faa_id())
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let has_temp = self.temp_c.to_celsius().is_some();
+        let has_wind = self.wind_knots().is_some();
+        let has_visibility = self.visibility_statute_mi.to_statute_miles().is_some();
+
+        !self.raw_text.trim().is_empty() && (has_temp || has_wind || has_visibility)
+    }
+
+    /// Returns the observation time, falling back to the current time when the feed
+    /// omitted it. Only use this where a timestamp is mandatory; the fallback makes a
+    /// report of unknown age look brand new.
+    pub fn observation_time_or_now(&self) -> chrono::DateTime<Utc> {
+        self.observation_time.unwrap_or_else(Utc::now)
+    }
+
+    /// Converts the Zulu observation time into the station's local zone.
+    ///
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use metars::MetarBuilder;
+    ///
+    /// let metar = MetarBuilder::new("KSJC")
+    ///     .observation_time(Utc.with_ymd_and_hms(2024, 11, 5, 17, 53, 0).unwrap())
+    ///     .build();
+    ///
+    /// let local = metar.observation_time_local(chrono_tz::US::Pacific).unwrap();
+    ///
+    /// assert_eq!(local.format("%-I:%M %p %Z").to_string(), "9:53 AM PST");
+    /// ```
+    #[cfg(feature = "chrono-tz")]
+    pub fn observation_time_local(
+        &self,
+        tz: chrono_tz::Tz,
+    ) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        self.observation_time.map(|x| x.with_timezone(&tz))
+    }
+
+    pub fn age_minutes(&self) -> Option<i64> {
+        self.observation_time
+            .map(|val| (Utc::now() - val).num_minutes())
+    }
+
+    /// Reported flight category, falling back to the computed one, paired with whether
+    /// the report is older than `max_age_min`. A report without a time counts as stale.
+    pub fn category_with_freshness(&self, max_age_min: i64) -> Option<(&str, Freshness)> {
+        let category = self
+            .flight_category
+            .as_deref()
+            .or_else(|| self.derived_flight_category())?;
+
+        let freshness = match self.age_minutes() {
+            Some(val) if val <= max_age_min => Freshness::Fresh,
+            _ => Freshness::Stale,
+        };
+
+        Some((category, freshness))
+    }
+
+    pub fn is_cavok(&self) -> bool {
+        has_cavok_group(&self.raw_text)
+    }
+
+    /// Whether the report carries the `NOSIG` trend, forecasting no significant change
+    /// over the next two hours.
+    pub fn no_significant_change(&self) -> bool {
+        self.raw_text
+            .split_whitespace()
+            .take_while(|&x| x != "RMK")
+            .any(|x| x == "NOSIG")
+    }
+
+    pub fn station_type(&self) -> Option<&str> {
+        self.remarks
+            .as_deref()?
+            .split_whitespace()
+            .find(|&x| x == "AO1" || x == "AO2")
+    }
+
+    pub fn is_automated(&self) -> bool {
+        self.station_type().is_some()
+    }
+
+    pub fn has_precip_sensor(&self) -> bool {
+        self.station_type() == Some("AO2")
+    }
+
+    /// A `$` group flags an automated station due for maintenance, so its data may be off.
+    /// The feed's own flag wins when it was parsed; otherwise the raw text is checked.
+    pub fn needs_maintenance(&self) -> bool {
+        self.maintenance_indicator
+            .unwrap_or_else(|| self.raw_text.split_whitespace().any(|x| x == "$"))
+    }
+
+    pub fn rvr_unavailable(&self) -> bool {
+        self.rvr.iter().any(|x| x.not_available)
+    }
+
+    pub fn ceiling_ft(&self) -> Option<i32> {
+        self.clouds
+            .iter()
+            .filter(|cloud| {
+                matches!(
+                    cloud.sky_cover.as_deref(),
+                    Some("BKN" | "OVC" | "OVX" | "VV")
+                )
+            })
+            .filter_map(|cloud| cloud.cloud_base_ft_agl)
+            .min()
+    }
+
+    /// Always derived from ceiling and visibility, never from the feed's `flight_category`,
+    /// so the two can be compared to spot discrepancies.
+    pub fn computed_flight_category(&self) -> Option<String> {
+        self.derived_flight_category().map(String::from)
+    }
+
+    // Standard FAA bands; an absent ceiling counts as unlimited.
+    fn derived_flight_category(&self) -> Option<&'static str> {
+        let visibility = self.visibility_statute_mi.to_statute_miles()?;
+        let ceiling = self.ceiling_ft().unwrap_or(i32::MAX);
+
+        let category = if ceiling < 500 || visibility < 1.0 {
+            "LIFR"
+        } else if ceiling < 1000 || visibility < 3.0 {
+            "IFR"
+        } else if ceiling <= 3000 || visibility <= 5.0 {
+            "MVFR"
+        } else {
+            "VFR"
+        };
+
+        Some(category)
+    }
+
+    pub fn has_thunderstorm(&self) -> bool {
+        // Only whole weather groups count, so TSNO or an end time like TSE15 doesn't match.
+        let has_group = |val: &str| val.split_whitespace().any(is_thunderstorm_group);
+
+        self.wx_string.as_deref().is_some_and(has_group)
+            || self.remarks.as_deref().is_some_and(has_group)
+    }
+
+    pub fn has_freezing_precip(&self) -> bool {
+        self.wx_string.as_deref().is_some_and(|val| {
+            val.split_whitespace()
+                .any(|x| x.contains("FZRA") || x.contains("FZDZ"))
+        })
+    }
+
+    /// Near-freezing air with visible moisture, either a cloud layer or precipitation
+    /// and fog in the present weather.
+    pub fn icing_risk(&self) -> bool {
+        let near_freezing = self
+            .temp_c
+            .to_celsius()
+            .is_some_and(|val| (-10.0..=2.0).contains(&val));
+
+        let has_cloud = self.clouds.iter().any(|cloud| {
+            !matches!(
+                cloud.sky_cover.as_deref(),
+                None | Some("CLR" | "SKC" | "NSC" | "CAVOK")
+            )
+        });
+
+        let moisture = [
+            "RA", "SN", "DZ", "PL", "GR", "GS", "SG", "IC", "UP", "FG", "BR",
+        ];
+
+        let has_moisture = self.wx_string.as_deref().is_some_and(|val| {
+            val.split_whitespace()
+                .any(|x| weather_group_has(x, &moisture))
+        });
+
+        near_freezing && (has_cloud || has_moisture)
+    }
+
+    /// Estimated with the standard 2°C per 1,000 ft lapse rate; at or below freezing
+    /// the freezing level is the field itself.
+    pub fn freezing_level_ft_msl(&self) -> Option<f64> {
+        let elevation = self.elevation_ft.to_feet()?;
+        let temp = self.temp_c.to_celsius()?;
+
+        finite((temp.max(0.0) / 2.0).mul_add(1000.0, elevation))
+    }
+
+    /// Breaks down the first precipitation group in `wx_string`.
+    pub fn precipitation(&self) -> Option<Precip> {
+        self.wx_string
+            .as_deref()?
+            .split_whitespace()
+            .find_map(Precip::parse_group)
+    }
+
+    pub fn has_low_ceiling(&self, threshold_ft: i32) -> bool {
+        self.ceiling_ft().is_some_and(|val| val < threshold_ft)
+    }
+
+    pub fn is_hazardous(&self) -> bool {
+        self.has_thunderstorm() || self.has_freezing_precip() || self.has_low_ceiling(1000)
+    }
+
+    /// Worse of the visibility and ceiling states; a sky with no BKN/OVC layer is unlimited.
+    pub fn color_state(&self) -> Option<ColorState> {
+        let visibility = self.visibility_m.to_meters()?;
+        let ceiling = self.ceiling_ft().unwrap_or(i32::MAX);
+
+        let by_visibility = match visibility {
+            val if val >= 8000.0 => ColorState::Blu,
+            val if val >= 5000.0 => ColorState::Wht,
+            val if val >= 3700.0 => ColorState::Grn,
+            val if val >= 1600.0 => ColorState::Ylo,
+            val if val >= 800.0 => ColorState::Amb,
+            _ => ColorState::Red,
+        };
+
+        let by_ceiling = match ceiling {
+            2500.. => ColorState::Blu,
+            1500.. => ColorState::Wht,
+            700.. => ColorState::Grn,
+            300.. => ColorState::Ylo,
+            200.. => ColorState::Amb,
+            _ => ColorState::Red,
+        };
+
+        Some(by_visibility.max(by_ceiling))
+    }
+
+    pub fn ceiling_category(&self) -> Option<CeilingBand> {
+        let ceiling = self.ceiling_ft()?;
+
+        let band = match ceiling {
+            ..1000 => CeilingBand::Low,
+            1000..3000 => CeilingBand::Medium,
+            3000..=10000 => CeilingBand::High,
+            _ => CeilingBand::VeryHigh,
+        };
+
+        Some(band)
+    }
+
+    pub fn has_convective(&self) -> bool {
+        self.clouds.iter().any(|cloud| cloud.convective_type.is_some())
+    }
+
+    pub fn cloud_bases_ft_msl(&self) -> Vec<Option<f64>> {
+        let elevation = self.elevation_ft.to_feet();
+
+        self.clouds
+            .iter()
+            .map(|cloud| cloud.base_ft_msl(elevation))
+            .collect()
+    }
+
+    /// Decodes the `SLPppp` remark, where values below 500 sit above 1000 hPa.
+    pub fn sea_level_pressure_hpa(&self) -> Option<f64> {
+        let remarks = self.remarks.as_deref()?;
+
+        let digits = remarks
+            .split_whitespace()
+            .find_map(|x| x.strip_prefix("SLP"))?;
+
+        if digits.len() != 3 || !digits.bytes().all(|x| x.is_ascii_digit()) {
+            return None;
+        }
+
+        let tenths = digits.parse::<f64>().ok()? / 10.0;
+
+        if tenths < 50.0 {
+            finite(1000.0 + tenths)
+        } else {
+            finite(900.0 + tenths)
+        }
+    }
+
+    /// Decodes the `5appp` remark; the change is negative when pressure ends lower.
+    pub fn pressure_tendency(&self) -> Option<(PressureTrend, f64)> {
+        let remarks = self.remarks.as_deref()?;
+
+        let group = remarks.split_whitespace().find(|x| {
+            x.len() == 5 && x.starts_with('5') && x.bytes().all(|x| x.is_ascii_digit())
+        })?;
+
+        let digit = group.as_bytes()[1];
+        let trend = PressureTrend::from_digit(digit)?;
+        let change = group[2..].parse::<f64>().ok()? / 10.0;
+
+        let change = match digit {
+            b'5'..=b'8' => -change,
+            _ => change,
+        };
+
+        Some((trend, change))
+    }
+
+    pub fn peak_wind(&self) -> Option<PeakWind> {
+        PeakWind::parse_remarks(self.remarks.as_deref()?)
+    }
+
+    pub fn pressure_altitude_ft(&self) -> Option<f64> {
+        let elevation = self.elevation_ft.to_feet()?;
+        let altim = self.altim_in_hg?;
+
+        finite((29.92 - altim).mul_add(1000.0, elevation))
+    }
+
+    pub fn density_altitude_ft(&self) -> Option<f64> {
+        self.altitudes().map(|(_, density_altitude)| density_altitude)
+    }
+
+    pub fn altitudes(&self) -> Option<(f64, f64)> {
+        let pressure_altitude = self.pressure_altitude_ft()?;
+        let temp = self.temp_c.to_celsius()?;
+
+        let isa_temp = 2.0f64.mul_add(-pressure_altitude / 1000.0, 15.0);
+        let density_altitude = 120.0f64.mul_add(temp - isa_temp, pressure_altitude);
+
+        Some((pressure_altitude, finite(density_altitude)?))
+    }
+}
+
+impl fmt::Display for Metar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let missing = || String::from("--");
+
+        let time = self
+            .observation_time
+            .map_or_else(missing, |val| val.format("%H:%MZ").to_string());
+
+        let wind = match self.wind_knots() {
+            Some(speed) => {
+                let direction = match &self.wind_dir_degrees {
+                    WindDirection::Degrees(Some(val)) => format!("{val:03}"),
+                    WindDirection::Variable(_) => String::from("VRB"),
+                    WindDirection::Degrees(None) => missing(),
+                };
+
+                match self.wind_gust_knots() {
+                    Some(gust) => format!("{direction}@{speed:.0}G{gust:.0}kt"),
+                    _ => format!("{direction}@{speed:.0}kt"),
+                }
+            }
+            _ => missing(),
+        };
+
+        let visibility = match self.visibility_statute_mi.to_statute_miles() {
+            Some(val) => match self.visibility_statute_mi.qualifier() {
+                Some(VisibilityQualifier::GreaterThan) => format!("P{val}SM"),
+                Some(VisibilityQualifier::LessThan) => format!("M{val}SM"),
+                None => format!("{val}SM"),
+            },
+            None => missing(),
+        };
+
+        let clouds: Vec<String> = self.clouds.iter().filter_map(Cloud::to_group).collect();
+        let clouds = if clouds.is_empty() {
+            missing()
+        } else {
+            clouds.join(" ")
+        };
+
+        let temp = self
+            .temp_c
+            .to_celsius()
+            .map_or_else(missing, |val| format!("{val:.0}°C"));
+
+        let dewpoint = self
+            .dewpoint_c
+            .to_celsius()
+            .map_or_else(missing, |val| format!("{val:.0}°C"));
+
+        let altim = self
+            .altim_in_hg
+            .map_or_else(missing, |val| format!("{val:.2}inHg"));
+
+        let flight_category = self.flight_category.clone().unwrap_or_else(missing);
+
+        write!(
+            f,
+            "{}  {time}  Wind {wind}  {visibility}  {clouds}  {temp}/{dewpoint}  {altim}  {flight_category}",
+            self.station_id
+        )
+    }
+}
+
+impl Metars {
+    /// ```
+    /// use metars::{MetarBuilder, Metars};
+    ///
+    /// let metars = Metars {
+    ///     reports: vec![
+    ///         MetarBuilder::new("KSJC").flight_category("VFR").build(),
+    ///         MetarBuilder::new("KSFO").flight_category("IFR").build(),
+    ///     ],
+    /// };
+    ///
+    /// let vfr = metars
+    ///     .iter()
+    ///     .filter(|x| x.flight_category.as_deref() == Some("VFR"))
+    ///     .count();
+    ///
+    /// assert_eq!(vfr, 1);
+    /// assert_eq!(metars.len(), 2);
+    /// ```
+    pub fn iter(&self) -> std::slice::Iter<'_, Metar> {
+        self.reports.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reports.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reports.is_empty()
+    }
+
+    #[deprecated(note = "use `reports`; the list is no longer limited to CONUS stations")]
+    pub fn conus(&self) -> &[Metar] {
+        &self.reports
+    }
+
+    pub fn retain_fresh(&mut self, max_age: Duration) {
+        let now = Utc::now();
+
+        self.reports.retain(|metar| match metar.observation_time {
+            Some(val) => now - val <= max_age,
+            None => false,
+        });
+    }
+
+    pub fn discard_older_than(&mut self, minutes: i64, keep_unknown: bool) {
+        self.reports.retain(|metar| match metar.age_minutes() {
+            Some(val) => val <= minutes,
+            None => keep_unknown,
+        });
+    }
+
+    // A box whose min_lon is greater than its max_lon is treated as crossing the
+    // antimeridian, e.g. 170.0 to -170.0.
+    pub fn within_bbox(self, min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Self {
+        let reports = self
+            .reports
+            .into_iter()
+            .filter(|metar| match (metar.lat, metar.lon) {
+                (Some(lat), Some(lon)) => {
+                    let within_lon = if min_lon <= max_lon {
+                        (min_lon..=max_lon).contains(&lon)
+                    } else {
+                        lon >= min_lon || lon <= max_lon
+                    };
+
+                    (min_lat..=max_lat).contains(&lat) && within_lon
+                }
+                _ => false,
+            })
+            .collect();
+
+        Self { reports }
+    }
+
+    pub fn filter_flight_category(self, cats: &[&str]) -> Self {
+        let reports = self
+            .reports
+            .into_iter()
+            .filter(|metar| {
+                let category = metar
+                    .flight_category
+                    .as_deref()
+                    .or_else(|| metar.derived_flight_category());
+
+                category.is_some_and(|val| cats.iter().any(|x| x.eq_ignore_ascii_case(val)))
+            })
+            .collect();
+
+        Self { reports }
+    }
+
+    // Multi-hour windows carry several reports per station; the newest one stands for it.
+    fn latest_by_station(&self) -> BTreeMap<&str, &Metar> {
+        let mut latest: BTreeMap<&str, &Metar> = BTreeMap::new();
+
+        for metar in &self.reports {
+            latest
+                .entry(metar.station_id.as_str())
+                .and_modify(|val| {
+                    if metar.observation_time >= val.observation_time {
+                        *val = metar;
+                    }
+                })
+                .or_insert(metar);
+        }
+
+        latest
+    }
+
+    /// Compares the latest report per station; a station counts as changed when
+    /// [`Metar::same_conditions`] says its conditions differ.
+    pub fn diff_against(&self, prev: &Self) -> Vec<StationDelta> {
+        let current = self.latest_by_station();
+        let previous = prev.latest_by_station();
+
+        let mut deltas = Vec::new();
+
+        for (station_id, metar) in &current {
+            match previous.get(station_id) {
+                Some(val) if !val.same_conditions(metar) => {
+                    deltas.push(StationDelta::Changed(String::from(*station_id)));
+                }
+                Some(_) => {}
+                None => deltas.push(StationDelta::Added(String::from(*station_id))),
+            }
+        }
+
+        for station_id in previous.keys() {
+            if !current.contains_key(station_id) {
+                deltas.push(StationDelta::Removed(String::from(*station_id)));
+            }
+        }
+
+        deltas
+    }
+
+    pub fn nearest(&self, lat: f64, lon: f64) -> Option<&Metar> {
+        self.reports
+            .iter()
+            .filter_map(|metar| {
+                let distance = haversine_miles((lat, lon), (metar.lat?, metar.lon?))?;
+
+                Some((metar, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(metar, _)| metar)
+    }
+
+    pub fn summary_stats(&self) -> SummaryStats {
+        let mut stats = SummaryStats::default();
+
+        for metar in &self.reports {
+            let category = metar
+                .flight_category
+                .as_deref()
+                .or_else(|| metar.derived_flight_category());
+
+            if let Some(val) = category {
+                *stats
+                    .flight_categories
+                    .entry(val.to_ascii_uppercase())
+                    .or_default() += 1;
+            }
+
+            if let Some(val) = metar.ceiling_ft() {
+                if stats.lowest_ceiling.as_ref().is_none_or(|x| val < x.1) {
+                    stats.lowest_ceiling = Some((metar.station_id.clone(), val));
+                }
+            }
+
+            if let Some(val) = metar.wind_knots() {
+                if stats.highest_wind.as_ref().is_none_or(|x| val > x.1) {
+                    stats.highest_wind = Some((metar.station_id.clone(), val));
+                }
+            }
+
+            if let Some(val) = metar.visibility_statute_mi.to_statute_miles() {
+                if stats.lowest_visibility_statute_mi.is_none_or(|x| val < x) {
+                    stats.lowest_visibility_statute_mi = Some(val);
+                }
+            }
+        }
+
+        stats
+    }
+
+    pub fn to_dataframe(&self) -> Result<DataFrame, MetarError> {
+        let knots = |wind: &Wind| match *wind {
+            Wind::Mph(_) => None,
+            _ => wind.to_knots(),
+        };
+
+        let meters = |elevation: &Elevation| match *elevation {
+            Elevation::Meters(val) => val,
+            Elevation::Feet(_) => None,
+        };
+
+        let degrees = |direction: &WindDirection| match *direction {
+            WindDirection::Degrees(val) => val,
+            WindDirection::Variable(_) => None,
+        };
+
+        let metars = &self.reports;
+
+        let raw_text: Vec<_> = metars.iter().map(|x| x.raw_text.as_str()).collect();
+        let station_id: Vec<_> = metars.iter().map(|x| x.station_id.as_str()).collect();
+        let observation_time: Vec<_> = metars
+            .iter()
+            .map(|x| x.observation_time.map(|val| val.naive_utc()))
+            .collect();
+        let lat: Vec<_> = metars.iter().map(|x| x.lat).collect();
+        let lon: Vec<_> = metars.iter().map(|x| x.lon).collect();
+        let temp_c: Vec<_> = metars.iter().map(|x| x.temp_c.to_celsius()).collect();
+        let temp_f: Vec<_> = metars.iter().map(|x| x.temp_f.to_fahrenheit()).collect();
+        let dewpoint_c: Vec<_> = metars.iter().map(|x| x.dewpoint_c.to_celsius()).collect();
+        let dewpoint_f: Vec<_> = metars
+            .iter()
+            .map(|x| x.dewpoint_f.to_fahrenheit())
+            .collect();
+        let wind_dir_degrees: Vec<_> = metars
+            .iter()
+            .map(|x| degrees(&x.wind_dir_degrees))
+            .collect();
+        let wind_dir_cardinal: Vec<_> = metars
+            .iter()
+            .map(|x| x.wind_dir_cardinal.as_deref())
+            .collect();
+        let wind_speed_kt: Vec<_> = metars.iter().map(|x| knots(&x.wind_speed_kt)).collect();
+        let wind_speed_mph: Vec<_> = metars.iter().map(|x| x.wind_speed_mph.to_mph()).collect();
+        let wind_gust_kt: Vec<_> = metars.iter().map(|x| knots(&x.wind_gust_kt)).collect();
+        let wind_gust_mph: Vec<_> = metars.iter().map(|x| x.wind_gust_mph.to_mph()).collect();
+        let visibility_statute_mi: Vec<_> = metars
+            .iter()
+            .map(|x| x.visibility_statute_mi.to_statute_miles())
+            .collect();
+        let altim_in_hg: Vec<_> = metars.iter().map(|x| x.altim_in_hg).collect();
+        let wx_string: Vec<_> = metars.iter().map(|x| x.wx_string.as_deref()).collect();
+        let flight_category: Vec<_> = metars
+            .iter()
+            .map(|x| x.flight_category.as_deref())
+            .collect();
+        let report_type: Vec<_> = metars
+            .iter()
+            .map(|x| x.report_type.as_ref().map(ReportType::as_str))
+            .collect();
+        let elevation_m: Vec<_> = metars.iter().map(|x| meters(&x.elevation_m)).collect();
+        let elevation_ft: Vec<_> = metars.iter().map(|x| x.elevation_ft.to_feet()).collect();
+        let remarks: Vec<_> = metars.iter().map(|x| x.remarks.as_deref()).collect();
+
+        let dataframe = df!(
+            "raw_text" => raw_text,
+            "station_id" => station_id,
+            "observation_time" => observation_time,
+            "lat" => lat,
+            "lon" => lon,
+            "temp_c" => temp_c,
+            "temp_f" => temp_f,
+            "dewpoint_c" => dewpoint_c,
+            "dewpoint_f" => dewpoint_f,
+            "wind_dir_degrees" => wind_dir_degrees,
+            "wind_dir_cardinal" => wind_dir_cardinal,
+            "wind_speed_kt" => wind_speed_kt,
+            "wind_speed_mph" => wind_speed_mph,
+            "wind_gust_kt" => wind_gust_kt,
+            "wind_gust_mph" => wind_gust_mph,
+            "visibility_statute_mi" => visibility_statute_mi,
+            "altim_in_hg" => altim_in_hg,
+            "wx_string" => wx_string,
+            "flight_category" => flight_category,
+            "report_type" => report_type,
+            "elevation_m" => elevation_m,
+            "elevation_ft" => elevation_ft,
+            "remarks" => remarks,
+        )?;
+
+        Ok(dataframe)
+    }
+
+    pub fn write_parquet(&self, path: &str) -> Result<(), MetarError> {
+        let mut dataframe = self.to_dataframe()?;
+        let file = File::create(path)?;
+
+        ParquetWriter::new(file).finish(&mut dataframe)?;
+
+        Ok(())
+    }
+
+    pub fn write_csv(&self, path: &str) -> Result<(), MetarError> {
+        let mut dataframe = self.to_dataframe()?;
+        let file = File::create(path)?;
+
+        CsvWriter::new(file).finish(&mut dataframe)?;
+
+        Ok(())
+    }
+
+    pub fn to_geojson(&self) -> String {
+        let features: Vec<serde_json::Value> = self
+            .reports
+            .iter()
+            .filter_map(|metar| {
+                let (lat, lon) = (metar.lat?, metar.lon?);
+
+                let wind_dir_degrees = match &metar.wind_dir_degrees {
+                    WindDirection::Degrees(val) => json!(val),
+                    WindDirection::Variable(val) => json!(val),
+                };
+
+                let knots = |wind: &Wind| match *wind {
+                    Wind::Mph(_) => None,
+                    _ => wind.to_knots(),
+                };
+
+                Some(json!({
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "Point",
+                        "coordinates": [lon, lat],
+                    },
+                    "properties": {
+                        "station_id": metar.station_id,
+                        "observation_time": metar.observation_time.map(|x| x.to_rfc3339()),
+                        "flight_category": metar.flight_category,
+                        "temp_f": metar.temp_f.to_fahrenheit(),
+                        "wind_dir_degrees": wind_dir_degrees,
+                        "wind_speed_kt": knots(&metar.wind_speed_kt),
+                        "wind_gust_kt": knots(&metar.wind_gust_kt),
+                        "raw_text": metar.raw_text,
+                    },
+                }))
+            })
+            .collect();
+
+        json!({
+            "type": "FeatureCollection",
+            "features": features,
+        })
+        .to_string()
+    }
+
+    pub fn sorted_by<F>(self, cmp: F) -> Vec<Metar>
+    where
+        F: FnMut(&Metar, &Metar) -> Ordering,
+    {
+        let mut metars = self.reports;
+        metars.sort_by(cmp);
+
+        metars
+    }
+
+    pub fn sort_by_time(&mut self) {
+        self.reports.sort_by(cmp_observation_time);
+    }
+
+    pub fn sorted_by_time(mut self) -> Self {
+        self.sort_by_time();
+        self
+    }
+
+    pub fn group_by_station(self) -> HashMap<String, Vec<Metar>> {
+        let mut groups: HashMap<String, Vec<Metar>> = HashMap::new();
+
+        for metar in self.reports {
+            if metar.station_id.is_empty() {
+                continue;
+            }
+
+            groups
+                .entry(metar.station_id.clone())
+                .or_default()
+                .push(metar);
+        }
+
+        for metars in groups.values_mut() {
+            metars.sort_by(cmp_observation_time);
+        }
+
+        groups
+    }
+
+    pub fn map_reduce<T, M, R>(&self, map: M, reduce: R, init: T) -> T
+    where
+        T: Send,
+        M: Fn(&Metar) -> T + Sync + Send,
+        R: Fn(T, T) -> T + Sync + Send,
+    {
+        match self.reports.par_iter().map(&map).reduce_with(&reduce) {
+            Some(val) => reduce(init, val),
+            None => init,
+        }
+    }
+
+    pub fn nearest_batch(&self, coords: &[(f64, f64)]) -> Vec<Option<&Metar>> {
+        coords
+            .par_iter()
+            .map(|&(lat, lon)| self.nearest(lat, lon))
+            .collect()
+    }
+}
+
+impl Deref for Metars {
+    type Target = [Metar];
+
+    fn deref(&self) -> &Self::Target {
+        &self.reports
+    }
+}
+
+impl IntoIterator for Metars {
+    type Item = Metar;
+    type IntoIter = std::vec::IntoIter<Metar>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.reports.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Metars {
+    type Item = &'a Metar;
+    type IntoIter = std::slice::Iter<'a, Metar>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.reports.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validity_period_rolls_over_the_month() {
+        let reference = Utc.with_ymd_and_hms(2024, 1, 31, 6, 0, 0).unwrap();
+
+        let (start, end) = parse_validity_period("0112/0218", reference).unwrap();
+
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 2, 1, 12, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2024, 2, 2, 18, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn time_group_resolves_across_a_month_boundary() {
+        let reference = Utc.with_ymd_and_hms(2024, 3, 1, 0, 10, 0).unwrap();
+
+        assert_eq!(
+            parse_time_group("KSJC 292355Z 18010KT 10SM", reference),
+            Utc.with_ymd_and_hms(2024, 2, 29, 23, 55, 0).single()
+        );
+
+        let reference = Utc.with_ymd_and_hms(2024, 2, 29, 23, 58, 0).unwrap();
+
+        assert_eq!(
+            parse_time_group("KSJC 010005Z 18010KT 10SM", reference),
+            Utc.with_ymd_and_hms(2024, 3, 1, 0, 5, 0).single()
+        );
+    }
+
+    #[test]
+    fn parses_m_prefixed_temperatures() {
+        assert_eq!(parse_temperature("M05"), Some(-5.0));
+        assert_eq!(parse_temperature("05"), Some(5.0));
+        assert_eq!(parse_temperature("M"), None);
+        assert_eq!(
+            parse_temperature_dewpoint_group("KDEN 051753Z 36008KT 10SM M05/ A3012"),
+            Some((Some(-5.0), None))
+        );
+    }
+
+    #[test]
+    fn cache_is_fresh_only_within_the_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metars.gz");
+
+        fs::write(&path, b"").unwrap();
+
+        let ttl = std::time::Duration::from_secs(300);
+        let now = SystemTime::now();
+        let within = now + std::time::Duration::from_secs(60);
+        let past = now + std::time::Duration::from_secs(600);
+
+        assert!(is_fresh(&path, ttl, within));
+        assert!(!is_fresh(&path, ttl, past));
+        assert!(!is_fresh(&dir.path().join("missing.gz"), ttl, now));
+    }
+
+    #[test]
+    fn truncated_archive_is_a_download_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metars.gz");
+        let out_path = dir.path().join("metars.csv");
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(b"raw_text,station_id\n".repeat(100).as_slice())
+            .unwrap();
+        let archive = encoder.finish().unwrap();
+
+        fs::write(&path, &archive[..archive.len() / 2]).unwrap();
+
+        let result = extract_file(path.to_str().unwrap(), out_path.to_str().unwrap(), true);
+
+        assert!(matches!(result, Err(MetarError::Download(_))));
+    }
+}
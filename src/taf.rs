@@ -0,0 +1,256 @@
+use chrono::Utc;
+use polars::frame::DataFrame;
+
+use crate::{
+    download_file, extract_file, is_weather_group, nearest_day_time, parse_validity_period,
+    parse_visibility_group, parse_wind_group, read_cache_file, Cloud, MetarError, Wind,
+    WindDirection, DEFAULT_USER_AGENT,
+};
+
+#[derive(Debug)]
+pub struct TafForecast {
+    pub change_indicator: Option<String>,
+    pub valid_from: Option<chrono::DateTime<Utc>>,
+    pub valid_to: Option<chrono::DateTime<Utc>>,
+    pub wind_dir_degrees: WindDirection,
+    pub wind_speed_kt: Wind,
+    pub wind_gust_kt: Wind,
+    pub visibility_statute_mi: Option<f64>,
+    pub wx_string: Option<String>,
+    pub clouds: Vec<Cloud>,
+}
+
+impl TafForecast {
+    fn new(
+        change_indicator: Option<String>,
+        valid_from: Option<chrono::DateTime<Utc>>,
+        valid_to: Option<chrono::DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            change_indicator,
+            valid_from,
+            valid_to,
+            wind_dir_degrees: WindDirection::Degrees(None),
+            wind_speed_kt: Wind::Knots(None),
+            wind_gust_kt: Wind::Knots(None),
+            visibility_statute_mi: None,
+            wx_string: None,
+            clouds: Vec::new(),
+        }
+    }
+
+    fn is_from_group(&self) -> bool {
+        match &self.change_indicator {
+            Some(val) => val == "FM",
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Taf {
+    pub raw_text: String,
+    pub station_id: String,
+    pub issue_time: Option<chrono::DateTime<Utc>>,
+    pub valid_from: Option<chrono::DateTime<Utc>>,
+    pub valid_to: Option<chrono::DateTime<Utc>>,
+    pub forecasts: Vec<TafForecast>,
+}
+
+#[derive(Debug)]
+pub struct Tafs {
+    pub reports: Vec<Taf>,
+}
+
+impl Tafs {
+    #[deprecated(note = "use `reports`, matching `Metars`")]
+    pub fn conus(&self) -> &[Taf] {
+        &self.reports
+    }
+}
+
+impl Taf {
+    pub async fn fetch_tafs() -> Result<(), MetarError> {
+        let url = "https://aviationweather.gov/data/cache/tafs.cache.csv.gz";
+
+        download_file(url, "./tafs.gz", None, 0, DEFAULT_USER_AGENT).await
+    }
+
+    pub fn extract_taf_file(path: &str) -> Result<(), MetarError> {
+        extract_file(path, "./tafs.csv", false)
+    }
+
+    pub fn read_taf_file(path: &str) -> Result<DataFrame, MetarError> {
+        read_cache_file(path)
+    }
+
+    pub fn parse_tafs(dataframe: &DataFrame) -> Result<Tafs, MetarError> {
+        let column = |name: &str| {
+            dataframe
+                .get_column_index(name)
+                .ok_or_else(|| MetarError::Schema(format!("missing column `{name}`")))
+        };
+
+        let raw_text_column = column("raw_text")?;
+        let station_id_column = column("station_id")?;
+        let issue_time_column = column("issue_time")?;
+
+        let mut tafs: Vec<Self> = Vec::new();
+
+        for i in 0..dataframe.height() {
+            if let Some(row) = dataframe.get(i) {
+                let station_id = row[station_id_column].str_value().to_string();
+
+                if !station_id.starts_with('K') {
+                    continue;
+                }
+
+                let raw_text = row[raw_text_column].str_value().to_string();
+
+                let duplicate = tafs
+                    .last()
+                    .is_some_and(|x| x.station_id == station_id && x.raw_text == raw_text);
+
+                if duplicate {
+                    continue;
+                }
+
+                let issue_time: Option<chrono::DateTime<Utc>> = if row[issue_time_column].is_null()
+                {
+                    None
+                } else {
+                    row[issue_time_column].str_value().parse().ok()
+                };
+
+                let (valid_from, valid_to, forecasts) = Self::parse_raw_text(&raw_text, issue_time);
+
+                let taf = Self {
+                    raw_text,
+                    station_id,
+                    issue_time,
+                    valid_from,
+                    valid_to,
+                    forecasts,
+                };
+
+                tafs.push(taf);
+            }
+        }
+
+        Ok(Tafs { reports: tafs })
+    }
+
+    fn parse_raw_text(
+        raw_text: &str,
+        issue_time: Option<chrono::DateTime<Utc>>,
+    ) -> (
+        Option<chrono::DateTime<Utc>>,
+        Option<chrono::DateTime<Utc>>,
+        Vec<TafForecast>,
+    ) {
+        let reference = issue_time.unwrap_or_else(Utc::now);
+
+        let mut groups = raw_text
+            .split_whitespace()
+            .take_while(|&x| x != "RMK")
+            .peekable();
+
+        let mut validity = None;
+
+        for group in groups.by_ref() {
+            if group.contains('/') && group.len() == 9 {
+                validity = parse_validity_period(group, reference);
+                break;
+            }
+        }
+
+        let (valid_from, valid_to) = match validity {
+            Some((start, end)) => (Some(start), Some(end)),
+            None => (None, None),
+        };
+
+        let mut forecasts = vec![TafForecast::new(None, valid_from, None)];
+
+        while let Some(group) = groups.next() {
+            if let Some(time) = group.strip_prefix("FM") {
+                if time.len() == 6 && time.bytes().all(|x| x.is_ascii_digit()) {
+                    let day = time[..2].parse::<u32>().unwrap_or(0);
+                    let hour = time[2..4].parse::<u32>().unwrap_or(0);
+                    let minute = time[4..].parse::<u32>().unwrap_or(0);
+
+                    let start = nearest_day_time(reference, day, hour, minute);
+
+                    forecasts.push(TafForecast::new(Some(String::from("FM")), start, None));
+                    continue;
+                }
+            }
+
+            if group == "TEMPO" || group == "BECMG" || group.starts_with("PROB") {
+                let mut change_indicator = String::from(group);
+
+                if group.starts_with("PROB") && groups.peek() == Some(&"TEMPO") {
+                    change_indicator.push_str(" TEMPO");
+                    groups.next();
+                }
+
+                let period = match groups.peek() {
+                    Some(val) if val.contains('/') && val.len() == 9 => {
+                        let period = parse_validity_period(val, reference);
+                        groups.next();
+                        period
+                    }
+                    _ => None,
+                };
+
+                let (start, end) = match period {
+                    Some((start, end)) => (Some(start), Some(end)),
+                    None => (None, None),
+                };
+
+                forecasts.push(TafForecast::new(Some(change_indicator), start, end));
+                continue;
+            }
+
+            let Some(forecast) = forecasts.last_mut() else {
+                continue;
+            };
+
+            if let Some((wind_dir_degrees, wind_speed_kt, wind_gust_kt)) = parse_wind_group(group) {
+                forecast.wind_dir_degrees = wind_dir_degrees;
+                forecast.wind_speed_kt = wind_speed_kt;
+                forecast.wind_gust_kt = wind_gust_kt;
+            } else if group.bytes().all(|x| x.is_ascii_digit())
+                && group.len() == 1
+                && groups.peek().is_some_and(|x| x.ends_with("SM"))
+            {
+                let whole = group.parse::<f64>().unwrap_or(0.0);
+                let fraction = groups.next().and_then(parse_visibility_group);
+
+                forecast.visibility_statute_mi = fraction.map(|x| whole + x);
+            } else if let Some(val) = parse_visibility_group(group) {
+                forecast.visibility_statute_mi = Some(val);
+            } else if let Some(cloud) = Cloud::parse_group(group) {
+                forecast.clouds.push(cloud);
+            } else if is_weather_group(group) {
+                forecast.wx_string = match forecast.wx_string.take() {
+                    Some(val) => Some(format!("{val} {group}")),
+                    None => Some(String::from(group)),
+                };
+            }
+        }
+
+        let from_starts: Vec<Option<chrono::DateTime<Utc>>> = forecasts
+            .iter()
+            .filter(|x| x.is_from_group())
+            .map(|x| x.valid_from)
+            .collect();
+
+        let mut next_starts = from_starts.into_iter().skip(1);
+
+        for forecast in forecasts.iter_mut().filter(|x| x.is_from_group()) {
+            forecast.valid_to = next_starts.next().unwrap_or(valid_to);
+        }
+
+        (valid_from, valid_to, forecasts)
+    }
+}
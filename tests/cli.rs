@@ -0,0 +1,91 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/metars.cache.csv"
+);
+
+fn run_file(file: &str, args: &[&str]) -> Output {
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_metars"))
+        .args(["--file", file])
+        .args(args)
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+
+    // The working directory is the user's, so nothing should be left behind in it.
+    assert!(fs::read_dir(dir.path()).unwrap().next().is_none());
+
+    output
+}
+
+fn run(args: &[&str]) -> Output {
+    run_file(FIXTURE, args)
+}
+
+#[test]
+fn quiet_keeps_stderr_empty() {
+    let output = run(&["--quiet"]);
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("KSJC"));
+
+    assert!(!run(&[]).stderr.is_empty());
+}
+
+#[test]
+fn ndjson_prints_one_line_per_station() {
+    let output = run(&["--quiet", "--format", "ndjson"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(stdout.lines().count(), 5);
+
+    for line in stdout.lines() {
+        let json: serde_json::Value = serde_json::from_str(line).unwrap();
+
+        assert!(json["station_id"].is_string());
+    }
+}
+
+#[test]
+fn detects_gzip_without_the_extension() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("metars.cache");
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&fs::read(FIXTURE).unwrap()).unwrap();
+    fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+    let output = run_file(path.to_str().unwrap(), &["--quiet"]);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("KSJC"));
+}
+
+#[test]
+fn removes_the_temp_copy_when_parsing_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let path = dir.path().join("metars.csv");
+
+    fs::write(&path, "No errors\nNo warnings\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_metars"))
+        .args(["--file", path.to_str().unwrap(), "--quiet"])
+        .current_dir(dir.path())
+        .env("TMPDIR", tmp.path())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(fs::read_dir(tmp.path()).unwrap().next().is_none());
+}
@@ -0,0 +1,176 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use metars::{fetch_all, FetchConfig, Metar, MetarError};
+
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/metars.cache.csv"
+);
+
+fn respond(mut stream: TcpStream, body: &[u8]) {
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+
+    // Drain the request headers up to the blank line.
+    while reader.read_line(&mut line).unwrap() > 2 {
+        line.clear();
+    }
+
+    let header = format!(
+        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+
+    stream.write_all(header.as_bytes()).unwrap();
+    stream.write_all(body).unwrap();
+}
+
+// Answers a single request with `body`, standing in for the aviationweather.gov cache.
+fn serve_once(body: Vec<u8>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        respond(stream, &body);
+    });
+
+    format!("http://{addr}/metars.cache.csv.gz")
+}
+
+// Answers every request with `body` and counts how many arrived.
+fn serve_counting(body: Vec<u8>) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&hits);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            counter.fetch_add(1, Ordering::SeqCst);
+            respond(stream.unwrap(), &body);
+        }
+    });
+
+    (format!("http://{addr}/metars.cache.csv.gz"), hits)
+}
+
+fn gzip_fixture() -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&fs::read(FIXTURE).unwrap()).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[tokio::test]
+async fn fetches_from_a_configured_url() {
+    let url = serve_once(gzip_fixture());
+
+    let dataframe = Metar::fetch_metar_dataframe(&FetchConfig::new().url(url))
+        .await
+        .unwrap();
+
+    let metars = Metar::parse_metars(&dataframe);
+
+    assert_eq!(metars.len(), 5);
+}
+
+#[tokio::test]
+async fn combines_allowlist_and_blocklist_with_prefixes() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let config = FetchConfig::new()
+        .url(serve_once(gzip_fixture()))
+        .cache_dir(dir.path().join("cache"))
+        .output_path(dir.path().join("metars.gz").to_string_lossy())
+        .allowlist(["KSJC", "KSFO", "KDEN", "CYVR"])
+        .blocklist(["KSFO"]);
+
+    let metars = Metar::fetch_and_parse(&config).await.unwrap();
+
+    let stations: Vec<&str> = metars.iter().map(|x| x.station_id.as_str()).collect();
+
+    assert_eq!(stations, ["KSJC", "KDEN"]);
+}
+
+#[tokio::test]
+async fn fetch_all_keeps_results_when_one_url_fails() {
+    let body = b"metars".to_vec();
+    let url = serve_once(body.clone());
+
+    // Nothing listens on a port whose listener has already been dropped.
+    let closed = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let closed = format!("http://{closed}/metars.cache.csv.gz");
+
+    let config = FetchConfig::new().timeout(std::time::Duration::from_secs(5));
+    let results = fetch_all(&config, &[&url, &closed]).await;
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap(), &body);
+    assert!(results[1].is_err());
+}
+
+#[tokio::test]
+async fn streams_a_feed_spanning_many_chunks() {
+    let fixture = fs::read_to_string(FIXTURE).unwrap();
+    let (preamble, rows) = fixture.split_at(fixture.find("KSJC ").unwrap());
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(preamble.as_bytes()).unwrap();
+
+    for _ in 0..500 {
+        encoder.write_all(rows.as_bytes()).unwrap();
+    }
+
+    let url = serve_once(encoder.finish().unwrap());
+
+    let dataframe = Metar::fetch_metar_dataframe(&FetchConfig::new().url(url))
+        .await
+        .unwrap();
+
+    assert_eq!(dataframe.height(), 6 * 500);
+}
+
+#[tokio::test]
+async fn rejects_a_truncated_archive() {
+    let archive = gzip_fixture();
+
+    // The Content-Length matches what is sent, so only the decoder can notice.
+    let url = serve_once(archive[..archive.len() / 2].to_vec());
+
+    let result = Metar::fetch_metar_dataframe(&FetchConfig::new().url(url)).await;
+
+    assert!(matches!(result, Err(MetarError::Download(_))));
+}
+
+#[tokio::test]
+async fn reuses_the_cache_within_the_ttl() {
+    let dir = tempfile::tempdir().unwrap();
+    let (url, hits) = serve_counting(gzip_fixture());
+
+    let config = FetchConfig::new()
+        .url(url)
+        .cache_dir(dir.path().join("cache"))
+        .output_path(dir.path().join("metars.gz").to_string_lossy());
+
+    Metar::fetch_and_parse(&config).await.unwrap();
+    let metars = Metar::fetch_and_parse(&config).await.unwrap();
+
+    assert_eq!(metars.len(), 5);
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    Metar::fetch_and_parse(&config.force_refresh(true))
+        .await
+        .unwrap();
+
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+}
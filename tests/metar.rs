@@ -0,0 +1,1224 @@
+use chrono::{Duration, Utc};
+use metars::{
+    haversine_miles, CeilingBand, ColorState, Freshness, Metar, MetarBuilder, Metars,
+    PrecipIntensity, PrecipType, PressureTrend, ReportType, StationDelta, Temperature, Visibility,
+    VisibilityQualifier, Wind, WindDirection,
+};
+
+#[test]
+fn icing_risk_with_cloud_near_freezing() {
+    let metar = MetarBuilder::new("KDEN")
+        .temp_c(-3.0)
+        .dewpoint_c(-5.0)
+        .cloud("OVC", 800)
+        .elevation_m(1640.0)
+        .build();
+
+    assert!(metar.icing_risk());
+    assert_eq!(metar.freezing_level_ft_msl(), metar.elevation_ft.to_feet());
+}
+
+#[test]
+fn no_icing_risk_when_warm() {
+    let metar = MetarBuilder::new("KSJC")
+        .temp_c(15.0)
+        .dewpoint_c(8.0)
+        .cloud("BKN", 1200)
+        .wx_string("-RA")
+        .build();
+
+    assert!(!metar.icing_risk());
+}
+
+#[test]
+fn icing_risk_matches_whole_weather_codes() {
+    let dust = MetarBuilder::new("KDEN")
+        .temp_c(-3.0)
+        .dewpoint_c(-12.0)
+        .wx_string("DUPO")
+        .build();
+
+    let mist = MetarBuilder::new("KDEN")
+        .temp_c(-3.0)
+        .dewpoint_c(-4.0)
+        .wx_string("BR")
+        .build();
+
+    assert!(!dust.icing_risk());
+    assert!(mist.icing_risk());
+}
+
+#[test]
+fn parses_peak_wind_from_remarks() {
+    let metar = MetarBuilder::new("KSFO")
+        .raw_text(
+            "KSFO 051756Z 29015G25KT 10SM BKN012 13/11 A3001 RMK AO2 PK WND 20045/1515 SLP163",
+        )
+        .build();
+
+    let peak_wind = metar.peak_wind().unwrap();
+
+    assert_eq!(
+        peak_wind.wind_dir_degrees,
+        WindDirection::Degrees(Some(200))
+    );
+    assert_eq!(peak_wind.wind_speed_kt, Wind::Knots(Some(45.0)));
+    assert_eq!(peak_wind.hour, Some(15));
+    assert_eq!(peak_wind.minute, 15);
+}
+
+#[test]
+fn peak_wind_is_none_without_the_remark() {
+    let metar = MetarBuilder::new("KSJC")
+        .raw_text("KSJC 051753Z 18010KT 10SM FEW250 15/08 A3005 RMK AO2 SLP176")
+        .build();
+
+    assert_eq!(metar.peak_wind(), None);
+}
+
+#[test]
+fn bearing_to_a_station_due_east() {
+    let from = MetarBuilder::new("KAAA").position(0.0, 0.0).build();
+    let to = MetarBuilder::new("KBBB").position(0.0, 1.0).build();
+
+    assert!((from.bearing_to(&to).unwrap() - 90.0).abs() < 1e-9);
+    assert_eq!(from.bearing_to_cardinal(&to).as_deref(), Some("E"));
+    assert_eq!(from.bearing_to(&Metar::default()), None);
+}
+
+#[test]
+fn decodes_precipitation_groups() {
+    let precipitation = |wx: &str| {
+        MetarBuilder::new("KBOS")
+            .wx_string(wx)
+            .build()
+            .precipitation()
+    };
+
+    let light_shower = precipitation("-SHRA BR").unwrap();
+
+    assert_eq!(light_shower.intensity, PrecipIntensity::Light);
+    assert_eq!(light_shower.types, [PrecipType::Rain]);
+    assert!(light_shower.showers);
+    assert!(!light_shower.vicinity);
+
+    let heavy_mix = precipitation("+RASN").unwrap();
+
+    assert_eq!(heavy_mix.intensity, PrecipIntensity::Heavy);
+    assert_eq!(heavy_mix.types, [PrecipType::Rain, PrecipType::Snow]);
+
+    let freezing = precipitation("FZDZ").unwrap();
+
+    assert_eq!(freezing.intensity, PrecipIntensity::Moderate);
+    assert!(freezing.freezing);
+
+    let vicinity = precipitation("VCSH").unwrap();
+
+    assert!(vicinity.vicinity);
+    assert!(vicinity.types.is_empty());
+
+    assert_eq!(precipitation("BR HZ"), None);
+    assert_eq!(MetarBuilder::new("KSJC").build().precipitation(), None);
+}
+
+#[test]
+fn flags_stale_flight_categories() {
+    let fresh = MetarBuilder::new("KSJC")
+        .observation_time(Utc::now())
+        .flight_category("VFR")
+        .build();
+
+    let stale = MetarBuilder::new("KSFO")
+        .observation_time(Utc::now() - Duration::hours(2))
+        .flight_category("IFR")
+        .build();
+
+    assert_eq!(
+        fresh.category_with_freshness(60),
+        Some(("VFR", Freshness::Fresh))
+    );
+    assert_eq!(
+        stale.category_with_freshness(60),
+        Some(("IFR", Freshness::Stale))
+    );
+}
+
+#[test]
+fn raw_string_round_trips() {
+    let raw_text =
+        "METAR KSFO 051756Z 29015G25KT 1 1/2SM -RA BR BKN012 OVC020 M01/M03 A3001 RMK AO2 SLP163";
+
+    let decoded = Metar::from_raw_text(raw_text);
+    let encoded = decoded.to_raw_string();
+    let redecoded = Metar::from_raw_text(&encoded);
+
+    assert_eq!(decoded.station_id, "KSFO");
+    assert_eq!(decoded.wind_gust_knots(), Some(25.0));
+    assert_eq!(decoded.visibility_statute_mi.to_statute_miles(), Some(1.5));
+    assert_eq!(decoded.temp_celsius(), Some(-1.0));
+    assert_eq!(decoded.altim_in_hg, Some(30.01));
+
+    assert_eq!(
+        Metar {
+            raw_text: String::new(),
+            ..redecoded
+        },
+        Metar {
+            raw_text: String::new(),
+            ..decoded
+        }
+    );
+}
+
+#[test]
+fn computed_flight_category_ignores_the_feed() {
+    let metar = MetarBuilder::new("KSJC")
+        .visibility_statute_mi(10.0)
+        .cloud("BKN", 2500)
+        .flight_category("VFR")
+        .build();
+
+    assert_eq!(metar.flight_category.as_deref(), Some("VFR"));
+    assert_eq!(metar.computed_flight_category().as_deref(), Some("MVFR"));
+}
+
+#[test]
+fn same_conditions_ignores_the_observation_time() {
+    let report = |minutes_ago: i64, speed_kt: f64| {
+        MetarBuilder::new("KSJC")
+            .observation_time(Utc::now() - Duration::minutes(minutes_ago))
+            .wind(180, speed_kt)
+            .visibility_statute_mi(10.0)
+            .cloud("FEW", 25000)
+            .temp_c(15.0)
+            .dewpoint_c(8.0)
+            .altim_in_hg(30.05)
+            .build()
+    };
+
+    assert!(report(60, 10.0).same_conditions(&report(0, 10.0)));
+    assert!(!report(60, 10.0).same_conditions(&report(0, 18.0)));
+}
+
+#[test]
+fn decodes_recent_weather_groups() {
+    let metar = Metar::from_raw_text("KDEN 051753Z 36008KT 10SM FEW080 M05/M07 A3012 RETS RERA");
+
+    assert_eq!(metar.recent_weather, ["Recent thunderstorm", "Recent rain"]);
+    assert!(
+        Metar::from_raw_text("KSJC 051753Z 18010KT 10SM FEW250 15/08 A3005")
+            .recent_weather
+            .is_empty()
+    );
+}
+
+#[test]
+fn summarizes_the_worst_conditions() {
+    let metars = Metars {
+        reports: vec![
+            MetarBuilder::new("KSJC")
+                .wind(180, 10.0)
+                .visibility_statute_mi(10.0)
+                .cloud("FEW", 25000)
+                .build(),
+            MetarBuilder::new("KSFO")
+                .wind(290, 25.0)
+                .visibility_statute_mi(3.0)
+                .cloud("BKN", 1200)
+                .build(),
+            MetarBuilder::new("KACV")
+                .visibility_statute_mi(0.25)
+                .cloud("VV", 200)
+                .build(),
+            MetarBuilder::new("KXXX").build(),
+        ],
+    };
+
+    let stats = metars.summary_stats();
+
+    assert_eq!(stats.flight_categories.get("VFR"), Some(&1));
+    assert_eq!(stats.flight_categories.get("MVFR"), Some(&1));
+    assert_eq!(stats.flight_categories.get("LIFR"), Some(&1));
+    assert_eq!(stats.lowest_ceiling, Some((String::from("KACV"), 200)));
+    assert_eq!(stats.highest_wind, Some((String::from("KSFO"), 25.0)));
+    assert_eq!(stats.lowest_visibility_statute_mi, Some(0.25));
+}
+
+#[test]
+fn decodes_wind_in_meters_per_second() {
+    let metar = Metar::from_raw_text("UUEE 051800Z 18010MPS 9999 FEW030 M02/M05 Q1015");
+
+    assert_eq!(metar.wind_speed_kt, Wind::MetersPerSecond(Some(10.0)));
+    assert_eq!(metar.wind_knots(), Some(19.0));
+    assert_eq!(metar.wind_mph(), Some(22.36));
+
+    let metar = Metar::from_raw_text("UUEE 051800Z 18036KMH 9999 FEW030 M02/M05 Q1015");
+
+    assert_eq!(metar.wind_speed_kt, Wind::KilometersPerHour(Some(36.0)));
+    assert_eq!(metar.wind_knots(), Some(19.0));
+}
+
+#[test]
+fn metric_wind_round_trips_in_its_own_unit() {
+    let decoded = Metar::from_raw_text("UUEE 051800Z 18010G15MPS 9999 FEW030 M02/M05 Q1015");
+    let encoded = decoded.to_raw_string();
+    let redecoded = Metar::from_raw_text(&encoded);
+
+    assert!(encoded.contains(" 18010G15MPS "), "{encoded}");
+    assert_eq!(redecoded.wind_speed_kt, Wind::MetersPerSecond(Some(10.0)));
+    assert_eq!(redecoded.wind_gust_kt, Wind::MetersPerSecond(Some(15.0)));
+
+    // The same wind reported in knots is not a change in conditions.
+    let in_knots = Metar {
+        wind_speed_kt: Wind::Knots(Some(19.0)),
+        wind_gust_kt: Wind::Knots(Some(29.0)),
+        ..decoded.clone()
+    };
+
+    assert!(decoded.same_conditions(&in_knots));
+}
+
+#[test]
+fn missing_elevation_sentinel_is_none_in_both_units() {
+    let metar = MetarBuilder::new("KXXX").elevation_m(9999.0).build();
+
+    assert_eq!(metar.elevation_meters(), None);
+    assert_eq!(metar.elevation_feet(), None);
+
+    let metar = MetarBuilder::new("KSJC").elevation_m(55.0).build();
+
+    assert_eq!(metar.elevation_meters(), Some(55.0));
+    assert_eq!(metar.elevation_feet(), Some(180.0));
+}
+
+#[test]
+fn detects_the_maintenance_indicator() {
+    let flagged = MetarBuilder::new("KSJC")
+        .raw_text("KSJC 051753Z 18010KT 10SM FEW250 15/08 A3005 RMK AO2 SLP176 $")
+        .build();
+
+    let clean = MetarBuilder::new("KSJC")
+        .raw_text("KSJC 051753Z 18010KT 10SM FEW250 15/08 A3005 RMK AO2 SLP176")
+        .build();
+
+    assert!(flagged.needs_maintenance());
+    assert!(!clean.needs_maintenance());
+
+    // The feed's flag takes precedence over the `$` in the raw text.
+    let cleared = Metar {
+        maintenance_indicator: Some(false),
+        ..flagged
+    };
+
+    assert!(!cleared.needs_maintenance());
+}
+
+#[test]
+fn decodes_every_cloud_layer() {
+    let metar = Metar::from_raw_text(
+        "KSEA 051753Z 18010KT 10SM FEW010 SCT020 BKN030 BKN040 OVC050 12/08 A3001",
+    );
+
+    let bases: Vec<Option<i32>> = metar.clouds.iter().map(|x| x.cloud_base_ft_agl).collect();
+
+    assert_eq!(
+        bases,
+        [Some(1000), Some(2000), Some(3000), Some(4000), Some(5000)]
+    );
+    assert_eq!(metar.clouds[4].sky_cover.as_deref(), Some("OVC"));
+}
+
+#[test]
+fn retain_fresh_drops_stale_and_untimed_reports() {
+    let report = |station_id: &str, minutes_ago: i64| {
+        MetarBuilder::new(station_id)
+            .observation_time(Utc::now() - Duration::minutes(minutes_ago))
+            .build()
+    };
+
+    let mut metars = Metars {
+        reports: vec![
+            report("KSJC", 10),
+            report("KSFO", 90),
+            MetarBuilder::new("KDEN").build(),
+            report("KBOS", 45),
+        ],
+    };
+
+    metars.retain_fresh(Duration::hours(1));
+
+    let stations: Vec<&str> = metars.iter().map(|x| x.station_id.as_str()).collect();
+
+    assert_eq!(stations, ["KSJC", "KBOS"]);
+}
+
+#[test]
+fn cloud_base_msl_adds_the_field_elevation() {
+    let metar = MetarBuilder::new("KSJC").cloud("BKN", 1000).build();
+    let cloud = &metar.clouds[0];
+
+    assert_eq!(cloud.base_ft_msl(Some(500.0)), Some(1500.0));
+    assert_eq!(cloud.base_ft_msl(None), None);
+    assert_eq!(metar.cloud_bases_ft_msl(), [None]);
+}
+
+#[test]
+fn altitudes_on_a_hot_day_at_a_high_field() {
+    let metar = MetarBuilder::new("KDEN")
+        .elevation_m(1646.0)
+        .altim_in_hg(30.02)
+        .temp_c(35.0)
+        .build();
+
+    let (pressure_altitude, density_altitude) = metar.altitudes().unwrap();
+
+    assert!((pressure_altitude - 5300.0).abs() < 1e-6);
+    assert!((density_altitude - 8972.0).abs() < 1e-6);
+    assert_eq!(
+        MetarBuilder::new("KDEN").temp_c(35.0).build().altitudes(),
+        None
+    );
+}
+
+#[test]
+fn density_altitude_matches_the_faa_chart() {
+    // The FAA density altitude chart puts 5,000 ft pressure altitude at 30°C near
+    // 8,000 ft.
+    let metar = MetarBuilder::new("KXXX")
+        .elevation_m(1524.0)
+        .altim_in_hg(29.92)
+        .temp_c(30.0)
+        .build();
+
+    assert_eq!(metar.pressure_altitude_ft(), Some(5000.0));
+    assert!((metar.density_altitude_ft().unwrap() - 8000.0).abs() < 300.0);
+
+    let low_altimeter = MetarBuilder::new("KXXX")
+        .elevation_m(1524.0)
+        .altim_in_hg(29.42)
+        .build();
+
+    assert!((low_altimeter.pressure_altitude_ft().unwrap() - 5500.0).abs() < 1e-6);
+    assert_eq!(low_altimeter.density_altitude_ft(), None);
+}
+
+#[test]
+fn wind_source_arrow_points_from_the_source() {
+    let arrow = |dir: i32| {
+        MetarBuilder::new("KSJC")
+            .wind(dir, 10.0)
+            .build()
+            .wind_source_arrow()
+    };
+
+    assert_eq!(arrow(0), Some('↑'));
+    assert_eq!(arrow(360), Some('↑'));
+    assert_eq!(arrow(90), Some('→'));
+    assert_eq!(Metar::default().wind_source_arrow(), None);
+}
+
+#[test]
+fn feels_like_temperatures_match_the_nws_tables() {
+    // 90°F at 70% humidity reads 106°F on the NWS heat index table.
+    let hot = MetarBuilder::new("KPHX")
+        .temp_c((90.0 - 32.0) / 1.8)
+        .dewpoint_c(26.0)
+        .build();
+
+    assert!((hot.heat_index_f().unwrap() - 106.0).abs() < 1.0);
+    assert_eq!(hot.wind_chill_f(), None);
+
+    // 0°F with a 15 mph wind reads -19°F on the NWS wind chill chart.
+    let cold = MetarBuilder::new("KMSP")
+        .temp_c(-32.0 / 1.8)
+        .wind(360, 13.04)
+        .build();
+
+    assert!((cold.wind_chill_f().unwrap() + 19.0).abs() < 1.0);
+    assert_eq!(cold.heat_index_f(), None);
+}
+
+#[test]
+fn splits_wind_into_runway_components() {
+    let components = |dir: i32| {
+        MetarBuilder::new("KSJC")
+            .wind(dir, 10.0)
+            .build()
+            .wind_components(360)
+            .unwrap()
+    };
+
+    let (headwind, crosswind) = components(360);
+    assert!((headwind - 10.0).abs() < 1e-9 && crosswind.abs() < 1e-9);
+
+    let (headwind, crosswind) = components(90);
+    assert!(headwind.abs() < 1e-9 && (crosswind - 10.0).abs() < 1e-9);
+
+    let (headwind, crosswind) = components(45);
+    assert!((headwind - 7.071).abs() < 1e-3 && (crosswind - 7.071).abs() < 1e-3);
+
+    let (headwind, _) = components(180);
+    assert!((headwind + 10.0).abs() < 1e-9);
+
+    assert_eq!(
+        Metar::from_raw_text("KSJC 051753Z VRB03KT 10SM").wind_components(360),
+        None
+    );
+}
+
+#[test]
+fn nearest_batch_answers_each_coordinate() {
+    let metars = Metars {
+        reports: vec![
+            MetarBuilder::new("KSJC")
+                .position(37.3591, -121.924)
+                .build(),
+            MetarBuilder::new("KDEN")
+                .position(39.8466, -104.6562)
+                .build(),
+            MetarBuilder::new("KBOS")
+                .position(42.3606, -71.0097)
+                .build(),
+        ],
+    };
+
+    let nearest: Vec<Option<&str>> = metars
+        .nearest_batch(&[(37.0, -122.0), (42.0, -71.5), (40.0, -105.0)])
+        .into_iter()
+        .map(|x| x.map(|metar| metar.station_id.as_str()))
+        .collect();
+
+    assert_eq!(nearest, [Some("KSJC"), Some("KBOS"), Some("KDEN")]);
+    assert_eq!(
+        Metars {
+            reports: Vec::new()
+        }
+        .nearest_batch(&[(0.0, 0.0)]),
+        [None]
+    );
+}
+
+#[test]
+fn string_map_flattens_the_fields() {
+    let map = MetarBuilder::new("KSJC")
+        .temp_c(15.0)
+        .wind(180, 10.0)
+        .cloud("BKN", 800)
+        .build()
+        .to_string_map();
+
+    assert_eq!(map["station_id"].as_deref(), Some("KSJC"));
+    assert_eq!(map["temp_c"].as_deref(), Some("15"));
+    assert_eq!(map["wind_dir_degrees"].as_deref(), Some("180"));
+    assert_eq!(map["wind_dir_cardinal"].as_deref(), Some("S"));
+    assert_eq!(map["clouds"].as_deref(), Some("BKN008"));
+    assert_eq!(map["wx_string"], None);
+}
+
+#[test]
+fn is_valid_needs_raw_text_and_an_observation() {
+    let complete = Metar::from_raw_text("KSJC 051753Z 18010KT 10SM FEW250 15/08 A3005");
+    let station_only = MetarBuilder::new("KSJC").build();
+
+    assert!(complete.is_valid());
+    assert!(!station_only.is_valid());
+}
+
+#[test]
+fn within_bbox_keeps_stations_inside_the_box() {
+    let metars = Metars {
+        reports: vec![
+            MetarBuilder::new("KSJC")
+                .position(37.3591, -121.924)
+                .build(),
+            MetarBuilder::new("KSFO")
+                .position(37.6196, -122.3656)
+                .build(),
+            MetarBuilder::new("KDEN")
+                .position(39.8466, -104.6562)
+                .build(),
+            MetarBuilder::new("KXXX").build(),
+        ],
+    };
+
+    let bay_area = metars.within_bbox(37.0, -123.0, 38.5, -121.5);
+    let stations: Vec<&str> = bay_area.iter().map(|x| x.station_id.as_str()).collect();
+
+    assert_eq!(stations, ["KSJC", "KSFO"]);
+
+    let pacific = Metars {
+        reports: vec![
+            MetarBuilder::new("NFFN").position(-17.755, 177.443).build(),
+            MetarBuilder::new("NSTU")
+                .position(-14.331, -170.711)
+                .build(),
+            MetarBuilder::new("YSSY").position(-33.946, 151.177).build(),
+        ],
+    };
+
+    let across_antimeridian = pacific.within_bbox(-20.0, 170.0, -10.0, -165.0);
+    let stations: Vec<&str> = across_antimeridian
+        .iter()
+        .map(|x| x.station_id.as_str())
+        .collect();
+
+    assert_eq!(stations, ["NFFN", "NSTU"]);
+}
+
+#[test]
+fn diffs_the_latest_report_per_station() {
+    let report = |station_id: &str, minutes_ago: i64, speed_kt: f64| {
+        MetarBuilder::new(station_id)
+            .observation_time(Utc::now() - Duration::minutes(minutes_ago))
+            .wind(180, speed_kt)
+            .build()
+    };
+
+    let prev = Metars {
+        reports: vec![
+            report("KSJC", 60, 10.0),
+            report("KSFO", 60, 15.0),
+            report("KDEN", 60, 8.0),
+        ],
+    };
+
+    let current = Metars {
+        reports: vec![
+            report("KSJC", 0, 10.0),
+            report("KSFO", 0, 25.0),
+            report("KSFO", 30, 15.0),
+            report("KBOS", 0, 12.0),
+        ],
+    };
+
+    assert_eq!(
+        current.diff_against(&prev),
+        [
+            StationDelta::Added(String::from("KBOS")),
+            StationDelta::Changed(String::from("KSFO")),
+            StationDelta::Removed(String::from("KDEN")),
+        ]
+    );
+}
+
+#[test]
+fn nearest_finds_the_closest_station() {
+    let metars = Metars {
+        reports: vec![
+            MetarBuilder::new("KXXX").build(),
+            MetarBuilder::new("KSJC")
+                .position(37.3591, -121.924)
+                .build(),
+            MetarBuilder::new("KSFO")
+                .position(37.6196, -122.3656)
+                .build(),
+            MetarBuilder::new("KDEN")
+                .position(39.8466, -104.6562)
+                .build(),
+        ],
+    };
+
+    let nearest = metars.nearest(37.6, -122.4).unwrap();
+
+    assert_eq!(nearest.station_id, "KSFO");
+    assert!(Metars {
+        reports: Vec::new()
+    }
+    .nearest(37.6, -122.4)
+    .is_none());
+}
+
+#[test]
+fn wind_summary_shows_both_units() {
+    let metar = MetarBuilder::new("KSJC")
+        .wind(180, 8.0)
+        .wind_gust_kt(20.0)
+        .build();
+
+    assert_eq!(
+        metar.wind_summary(true).as_deref(),
+        Some("180 at 8 kt (9 mph) gusting 20 kt (23 mph)")
+    );
+    assert_eq!(
+        metar.wind_summary(false).as_deref(),
+        Some("180 at 8 kt gusting 20 kt")
+    );
+}
+
+#[test]
+fn observation_time_or_now_falls_back_to_now() {
+    let observed = Utc::now() - Duration::hours(3);
+    let metar = MetarBuilder::new("KSJC").observation_time(observed).build();
+
+    assert_eq!(metar.observation_time_or_now(), observed);
+
+    let before = Utc::now();
+    let fallback = MetarBuilder::new("KSJC").build().observation_time_or_now();
+
+    assert!(fallback >= before && fallback <= Utc::now());
+}
+
+#[test]
+fn map_reduce_sums_wind_speeds() {
+    let metars = Metars {
+        reports: vec![
+            MetarBuilder::new("KSJC").wind(180, 10.0).build(),
+            MetarBuilder::new("KSFO").wind(290, 15.0).build(),
+            MetarBuilder::new("KDEN").wind(360, 8.0).build(),
+            MetarBuilder::new("KXXX").build(),
+        ],
+    };
+
+    let total = metars.map_reduce(|x| x.wind_knots().unwrap_or(0.0), |a, b| a + b, 0.0);
+
+    assert_eq!(total, 33.0);
+    assert_eq!(
+        Metars {
+            reports: Vec::new()
+        }
+        .map_reduce(|_| 1, |a, b| a + b, 0),
+        0
+    );
+}
+
+#[test]
+fn decodes_rvrno_as_not_available() {
+    let metar = Metar::from_raw_text(
+        "KSFO 051756Z 29015KT 1/4SM FG VV002 10/10 A3010 RMK AO2 RVRNO SLP193",
+    );
+
+    assert_eq!(metar.rvr.len(), 1);
+    assert!(metar.rvr[0].not_available);
+    assert_eq!(metar.rvr[0].min_ft, None);
+    assert!(metar.rvr_unavailable());
+
+    let without_rvr = Metar::from_raw_text("KSJC 051753Z 18010KT 10SM FEW250 15/08 A3005 RMK AO2");
+
+    assert!(without_rvr.rvr.is_empty());
+    assert!(!without_rvr.rvr_unavailable());
+}
+
+#[test]
+fn non_finite_values_convert_to_none() {
+    assert_eq!(
+        Temperature::Celsius(Some(f64::INFINITY)).to_fahrenheit(),
+        None
+    );
+    assert_eq!(Wind::Knots(Some(f64::NAN)).to_mph(), None);
+    assert_eq!(haversine_miles((f64::NAN, 0.0), (0.0, 0.0)), None);
+
+    let metar = MetarBuilder::new("KXXX")
+        .temp_c(f64::NAN)
+        .dewpoint_c(8.0)
+        .position(f64::NAN, 0.0)
+        .build();
+
+    assert_eq!(metar.relative_humidity(), None);
+    assert_eq!(metar.to_string_map()["temp_c"], None);
+    assert_eq!(metar.to_avwx_json()["temperature"], serde_json::Value::Null);
+    assert_eq!(
+        metar.bearing_to(&MetarBuilder::new("KSJC").position(0.0, 0.0).build()),
+        None
+    );
+}
+
+#[test]
+fn faa_id_strips_the_k_prefix() {
+    assert_eq!(MetarBuilder::new("KSJC").build().faa_id(), Some("SJC"));
+    assert_eq!(MetarBuilder::new("CYYZ").build().faa_id(), None);
+}
+
+#[test]
+fn sorted_by_orders_by_temperature_descending() {
+    let metars = Metars {
+        reports: vec![
+            MetarBuilder::new("KSJC").temp_c(15.0).build(),
+            MetarBuilder::new("KDEN").temp_c(-5.0).build(),
+            MetarBuilder::new("KPHX").temp_c(32.0).build(),
+        ],
+    };
+
+    let sorted = metars.sorted_by(|a, b| {
+        let temp = |x: &Metar| x.temp_celsius().unwrap_or(f64::MIN);
+        temp(b).total_cmp(&temp(a))
+    });
+
+    let stations: Vec<&str> = sorted.iter().map(|x| x.station_id.as_str()).collect();
+
+    assert_eq!(stations, ["KPHX", "KSJC", "KDEN"]);
+}
+
+#[test]
+fn checks_the_station_id_against_the_raw_text() {
+    let consistent = MetarBuilder::new("KSJC")
+        .raw_text("KSJC 051753Z 18010KT 10SM FEW250 15/08 A3005")
+        .build();
+
+    let short_form = MetarBuilder::new("KSJC")
+        .raw_text("SJC 051753Z 18010KT 10SM FEW250 15/08 A3005")
+        .build();
+
+    let misaligned = MetarBuilder::new("KSFO")
+        .raw_text("KSJC 051753Z 18010KT 10SM FEW250 15/08 A3005")
+        .build();
+
+    assert!(consistent.station_id_consistent());
+    assert!(short_form.station_id_consistent());
+    assert!(!misaligned.station_id_consistent());
+}
+
+#[test]
+fn buckets_the_ceiling_at_each_boundary() {
+    let category = |base: i32| {
+        MetarBuilder::new("KSJC")
+            .cloud("BKN", base)
+            .build()
+            .ceiling_category()
+    };
+
+    assert_eq!(category(999), Some(CeilingBand::Low));
+    assert_eq!(category(1000), Some(CeilingBand::Medium));
+    assert_eq!(category(2999), Some(CeilingBand::Medium));
+    assert_eq!(category(3000), Some(CeilingBand::High));
+    assert_eq!(category(10000), Some(CeilingBand::High));
+    assert_eq!(category(10100), Some(CeilingBand::VeryHigh));
+
+    let clear = MetarBuilder::new("KSJC").cloud("FEW", 2500).build();
+
+    assert_eq!(clear.ceiling_category(), None);
+}
+
+#[test]
+fn decodes_runway_visual_range_groups() {
+    let metar = Metar::from_raw_text(
+        "KSFO 051756Z 29015KT 1/4SM R28L/2600FT R06/2000V3000FT R10/M0600VP6000FT R24/1200FT/U FG VV002 10/10 A3010",
+    );
+
+    assert_eq!(metar.rvr.len(), 4);
+
+    let single = &metar.rvr[0];
+    assert_eq!(single.runway, "28L");
+    assert_eq!((single.min_ft, single.max_ft), (Some(2600), Some(2600)));
+
+    let variable = &metar.rvr[1];
+    assert_eq!((variable.min_ft, variable.max_ft), (Some(2000), Some(3000)));
+    assert!(!variable.less_than && !variable.greater_than);
+
+    let bounded = &metar.rvr[2];
+    assert_eq!((bounded.min_ft, bounded.max_ft), (Some(600), Some(6000)));
+    assert!(bounded.less_than && bounded.greater_than);
+
+    let trending = &metar.rvr[3];
+    assert_eq!(trending.trend.as_deref(), Some("U"));
+    assert_eq!(trending.trend_label.as_deref(), Some("Upward"));
+}
+
+#[test]
+fn avwx_json_follows_the_avwx_layout() {
+    let json = Metar::from_raw_text("KSFO 051756Z 29015G25KT 10SM -RA BKN012 OVC020 13/11 A3001")
+        .to_avwx_json();
+
+    assert_eq!(json["station"], "KSFO");
+    assert!(json["sanitized"].is_string());
+    assert_eq!(json["wind_direction"]["repr"], "290");
+    assert_eq!(json["wind_direction"]["value"], 290.0);
+    assert_eq!(json["wind_speed"]["value"], 15.0);
+    assert_eq!(json["wind_gust"]["value"], 25.0);
+    assert_eq!(json["altimeter"]["repr"], "30.01");
+    assert!(json["flight_rules"].is_null());
+    assert_eq!(json["wx_codes"], serde_json::json!(["-RA"]));
+
+    let clouds = json["clouds"].as_array().unwrap();
+
+    assert_eq!(clouds.len(), 2);
+    assert_eq!(clouds[0]["repr"], "BKN012");
+    assert_eq!(clouds[0]["type"], "BKN");
+    assert_eq!(clouds[0]["altitude"], 12);
+}
+
+#[test]
+fn decodes_sea_level_pressure_around_1000_hpa() {
+    let slp = |remarks: &str| {
+        MetarBuilder::new("KSFO")
+            .remarks(remarks)
+            .build()
+            .sea_level_pressure_hpa()
+    };
+
+    assert!((slp("AO2 SLP982").unwrap() - 998.2).abs() < 1e-9);
+    assert!((slp("AO2 SLP013").unwrap() - 1001.3).abs() < 1e-9);
+    assert!((slp("AO2 SLP132").unwrap() - 1013.2).abs() < 1e-9);
+    assert_eq!(slp("AO2 SLPNO"), None);
+    assert_eq!(slp("AO2"), None);
+}
+
+#[test]
+fn decodes_the_automated_station_type() {
+    let automated = MetarBuilder::new("KSJC").remarks("AO2 SLP176").build();
+    let manual = MetarBuilder::new("KSJC").remarks("SLP176").build();
+
+    assert_eq!(automated.station_type(), Some("AO2"));
+    assert!(automated.is_automated());
+    assert!(automated.has_precip_sensor());
+
+    assert_eq!(manual.station_type(), None);
+    assert!(!manual.is_automated());
+    assert!(!manual.has_precip_sensor());
+}
+
+#[test]
+fn refines_temperatures_from_the_t_group() {
+    let metar =
+        Metar::from_raw_text("KSJC 051753Z 18010KT 10SM FEW250 12/04 A3005 RMK AO2 T01230045");
+
+    assert_eq!(metar.temp_celsius(), Some(12.3));
+    assert_eq!(metar.dewpoint_c.to_celsius(), Some(4.5));
+
+    let metar =
+        Metar::from_raw_text("KDEN 051753Z 36008KT 10SM FEW080 01/M01 A3012 RMK AO2 T00121008");
+
+    assert_eq!(metar.temp_celsius(), Some(1.2));
+    assert_eq!(metar.dewpoint_c.to_celsius(), Some(-0.8));
+}
+
+#[test]
+fn cavok_clears_clouds_and_weather() {
+    let metar = Metar::from_raw_text("EGLL 051750Z 24008KT CAVOK 18/09 Q1021");
+
+    assert!(metar.is_cavok());
+    assert!(metar.clouds.is_empty());
+    assert_eq!(metar.wx_string, None);
+    assert_eq!(metar.visibility_statute_mi.to_statute_miles(), Some(6.21));
+    assert_eq!(
+        metar.visibility_statute_mi.qualifier(),
+        Some(VisibilityQualifier::GreaterThan)
+    );
+}
+
+#[test]
+fn report_type_round_trips_each_variant() {
+    let metar = ReportType::from("METAR");
+    let speci = ReportType::from("SPECI");
+    let unknown = ReportType::from("AUTO");
+
+    assert_eq!(metar, ReportType::Metar);
+    assert_eq!(speci, ReportType::Speci);
+    assert_eq!(unknown, ReportType::Unknown(String::from("AUTO")));
+
+    assert_eq!(metar.as_str(), "METAR");
+    assert_eq!(speci.as_str(), "SPECI");
+    assert_eq!(unknown.as_str(), "AUTO");
+
+    assert!(!metar.is_special());
+    assert!(speci.is_special());
+    assert!(!unknown.is_special());
+}
+
+#[test]
+fn decodes_metric_and_fractional_visibility() {
+    let metric = Metar::from_raw_text("LFPG 051800Z 27012KT 9999 FEW030 14/08 Q1018");
+
+    assert_eq!(metric.visibility_statute_mi.to_statute_miles(), Some(6.21));
+    assert_eq!(
+        metric.visibility_statute_mi.qualifier(),
+        Some(VisibilityQualifier::GreaterThan)
+    );
+
+    assert_eq!(metric.visibility_m.to_meters(), Some(9999.0));
+
+    let eight_km = Metar::from_raw_text("LFPG 051800Z 27012KT 8000 FEW030 14/08 Q1018");
+
+    assert_eq!(
+        eight_km.visibility_m,
+        Visibility::Meters(Some(8000.0), None)
+    );
+    assert_eq!(
+        eight_km.visibility_statute_mi.to_statute_miles(),
+        Some(4.97)
+    );
+
+    let fractional = Metar::from_raw_text("KSFO 051756Z 29015KT 1/2SM FG VV002 10/10 A3010");
+
+    assert_eq!(
+        fractional.visibility_statute_mi.to_statute_miles(),
+        Some(0.5)
+    );
+    assert_eq!(fractional.visibility_statute_mi.qualifier(), None);
+    assert_eq!(fractional.visibility_m.to_meters(), Some(805.0));
+}
+
+#[test]
+fn wet_bulb_matches_the_stull_approximation() {
+    // A 9.3°C dewpoint puts 20°C air at about 50% relative humidity.
+    let metar = MetarBuilder::new("KSJC")
+        .temp_c(20.0)
+        .dewpoint_c(9.3)
+        .build();
+
+    assert!((metar.relative_humidity().unwrap() - 50.0).abs() < 0.5);
+    assert!((metar.wet_bulb_c().unwrap() - 13.7).abs() < 0.5);
+}
+
+#[test]
+fn filters_to_ifr_and_lifr_stations() {
+    let metars = Metars {
+        reports: vec![
+            MetarBuilder::new("KSJC").flight_category("VFR").build(),
+            MetarBuilder::new("KSFO").flight_category("IFR").build(),
+            MetarBuilder::new("KACV").flight_category("LIFR").build(),
+            MetarBuilder::new("KOAK").flight_category("MVFR").build(),
+            // No category in the feed, so it is derived from the half-mile visibility.
+            MetarBuilder::new("KMRY").visibility_statute_mi(0.5).build(),
+        ],
+    };
+
+    let filtered = metars.filter_flight_category(&["IFR", "lifr"]);
+
+    let station_ids: Vec<&str> = filtered
+        .reports
+        .iter()
+        .map(|x| x.station_id.as_str())
+        .collect();
+
+    assert_eq!(station_ids, ["KSFO", "KACV", "KMRY"]);
+}
+
+#[test]
+fn gust_factor_needs_a_gust_above_the_wind() {
+    let gusty = MetarBuilder::new("KSFO")
+        .wind(280, 15.0)
+        .wind_gust_kt(25.0)
+        .build();
+
+    assert!(gusty.has_gust());
+    assert_eq!(gusty.gust_factor(), Some(10.0));
+
+    let steady = MetarBuilder::new("KSJC").wind(280, 15.0).build();
+
+    assert!(!steady.has_gust());
+    assert_eq!(steady.gust_factor(), None);
+
+    let equal = MetarBuilder::new("KOAK")
+        .wind(280, 15.0)
+        .wind_gust_kt(15.0)
+        .build();
+
+    assert!(!equal.has_gust());
+    assert_eq!(equal.gust_factor(), None);
+}
+
+#[test]
+fn groups_reports_by_station_in_time_order() {
+    let now = Utc::now();
+
+    let metars = Metars {
+        reports: vec![
+            MetarBuilder::new("KSJC")
+                .observation_time(now - Duration::hours(1))
+                .build(),
+            MetarBuilder::new("KSFO").observation_time(now).build(),
+            MetarBuilder::new("KSJC")
+                .observation_time(now - Duration::hours(3))
+                .build(),
+            MetarBuilder::new("KSJC")
+                .observation_time(now - Duration::hours(2))
+                .build(),
+        ],
+    };
+
+    let groups = metars.group_by_station();
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups["KSFO"].len(), 1);
+
+    let times: Vec<_> = groups["KSJC"].iter().map(|x| x.observation_time).collect();
+
+    assert_eq!(
+        times,
+        [
+            Some(now - Duration::hours(3)),
+            Some(now - Duration::hours(2)),
+            Some(now - Duration::hours(1)),
+        ]
+    );
+}
+
+#[test]
+fn sorts_by_time_with_untimed_reports_last() {
+    let now = Utc::now();
+
+    let mut metars = Metars {
+        reports: vec![
+            MetarBuilder::new("KOAK").build(),
+            MetarBuilder::new("KSJC").observation_time(now).build(),
+            MetarBuilder::new("KSFO")
+                .observation_time(now - Duration::hours(1))
+                .build(),
+        ],
+    };
+
+    metars.sort_by_time();
+
+    let station_ids: Vec<&str> = metars
+        .reports
+        .iter()
+        .map(|x| x.station_id.as_str())
+        .collect();
+
+    assert_eq!(station_ids, ["KSFO", "KSJC", "KOAK"]);
+}
+
+#[test]
+fn detects_thunderstorms_from_whole_weather_groups() {
+    let with_wx = |wx: &str| MetarBuilder::new("KDEN").wx_string(wx).build();
+
+    assert!(with_wx("+TSRA").has_thunderstorm());
+    assert!(with_wx("VCTS").has_thunderstorm());
+    assert!(!with_wx("FZRA").has_thunderstorm());
+
+    let ended = MetarBuilder::new("KDEN")
+        .raw_text("KDEN 051753Z 36008KT 10SM FEW080 22/12 A3012 RMK AO2 TSE15 SLP120")
+        .build();
+
+    assert!(!ended.has_thunderstorm());
+
+    let overhead = MetarBuilder::new("KDEN")
+        .raw_text("KDEN 051753Z 36008KT 10SM FEW080CB 22/12 A3012 RMK AO2 TS OHD MOV E")
+        .build();
+
+    assert!(overhead.has_thunderstorm());
+}
+
+#[test]
+fn decodes_rising_and_falling_pressure_tendencies() {
+    let tendency = |remarks: &str| {
+        MetarBuilder::new("KSJC")
+            .remarks(remarks)
+            .build()
+            .pressure_tendency()
+    };
+
+    assert_eq!(
+        tendency("AO2 SLP176 52032"),
+        Some((PressureTrend::Rising, 3.2))
+    );
+    assert_eq!(
+        tendency("AO2 SLP176 57015"),
+        Some((PressureTrend::Falling, -1.5))
+    );
+    assert_eq!(tendency("AO2 SLP176"), None);
+}
+
+#[test]
+fn color_state_changes_at_the_grn_boundaries() {
+    let with_visibility = |meters: f64| Metar {
+        visibility_m: Visibility::Meters(Some(meters), None),
+        ..MetarBuilder::new("EGLL").build()
+    };
+
+    assert_eq!(with_visibility(3700.0).color_state(), Some(ColorState::Grn));
+    assert_eq!(with_visibility(3699.0).color_state(), Some(ColorState::Ylo));
+
+    let with_ceiling = |base_ft_agl: i32| Metar {
+        visibility_m: Visibility::Meters(Some(9999.0), None),
+        ..MetarBuilder::new("EGLL").cloud("OVC", base_ft_agl).build()
+    };
+
+    assert_eq!(with_ceiling(700).color_state(), Some(ColorState::Grn));
+    assert_eq!(with_ceiling(699).color_state(), Some(ColorState::Ylo));
+
+    let cavok = Metar::from_raw_text("EGLL 051750Z 24008KT CAVOK 18/09 Q1021");
+    let clear = Metar::from_raw_text("KSJC 051753Z 18010KT 10SM SKC 15/08 A3005");
+
+    assert_eq!(cavok.color_state(), Some(ColorState::Blu));
+    assert_eq!(clear.color_state(), Some(ColorState::Blu));
+}
+
+#[test]
+fn color_state_rates_reported_meters_at_the_boundaries() {
+    let color_state = |group: &str| {
+        Metar::from_raw_text(&format!("EGLL 051750Z 24008KT {group} FEW040 18/09 Q1021"))
+            .color_state()
+    };
+
+    assert_eq!(color_state("8000"), Some(ColorState::Blu));
+    assert_eq!(color_state("3700"), Some(ColorState::Grn));
+    assert_eq!(color_state("1600"), Some(ColorState::Ylo));
+    assert_eq!(color_state("1599"), Some(ColorState::Amb));
+}
+
+#[test]
+fn decodes_wind_shear_groups() {
+    let layer = Metar::from_raw_text("KDEN 051753Z 36008KT 10SM FEW080 22/12 A3012 WS020/24045KT");
+
+    assert_eq!(layer.wind_shear.len(), 1);
+    assert_eq!(layer.wind_shear[0].height_ft, Some(2000));
+    assert_eq!(
+        layer.wind_shear[0].wind_dir_degrees,
+        WindDirection::Degrees(Some(240))
+    );
+    assert_eq!(layer.wind_shear[0].wind_speed_kt, Wind::Knots(Some(45.0)));
+    assert_eq!(layer.wind_shear[0].runway, None);
+
+    let runway = Metar::from_raw_text("KSFO 051756Z 29015KT 10SM FEW010 12/10 A3010 WS R28L");
+
+    assert_eq!(runway.wind_shear.len(), 1);
+    assert_eq!(runway.wind_shear[0].runway.as_deref(), Some("28L"));
+    assert!(!runway.wind_shear[0].all_runways);
+
+    let all = Metar::from_raw_text("KSFO 051756Z 29015KT 10SM FEW010 12/10 A3010 WS ALL RWY");
+
+    assert_eq!(all.wind_shear.len(), 1);
+    assert!(all.wind_shear[0].all_runways);
+    assert_eq!(all.wind_shear[0].runway, None);
+}
+
+#[test]
+fn fog_risk_follows_the_dewpoint_spread() {
+    let narrow = MetarBuilder::new("KSFO")
+        .temp_c(10.0)
+        .dewpoint_c(9.0)
+        .build();
+
+    assert_eq!(narrow.dewpoint_spread_c(), Some(1.0));
+    assert!(narrow.fog_risk());
+
+    let wide = MetarBuilder::new("KSJC")
+        .temp_c(25.0)
+        .dewpoint_c(10.0)
+        .build();
+
+    assert_eq!(wide.dewpoint_spread_c(), Some(15.0));
+    assert!(!wide.fog_risk());
+}
+
+#[test]
+fn metric_visibility_round_trips_in_meters() {
+    for (raw_text, group) in [
+        ("LFPG 051800Z 27012KT 0350 FG VV001 08/08 Q1018", " 0350 "),
+        ("LFPG 051800Z 27012KT 4000 BR BKN012 14/12 Q1018", " 4000 "),
+        ("LFPG 051800Z 27012KT 9999 FEW030 14/08 Q1018", " 9999 "),
+    ] {
+        let decoded = Metar::from_raw_text(raw_text);
+        let encoded = decoded.to_raw_string();
+        let redecoded = Metar::from_raw_text(&encoded);
+
+        assert!(encoded.contains(group), "{encoded}");
+        assert_eq!(
+            redecoded.visibility_statute_mi,
+            decoded.visibility_statute_mi
+        );
+        assert_eq!(redecoded.visibility_m, decoded.visibility_m);
+    }
+}
+
+#[test]
+fn detects_the_nosig_trend() {
+    let nosig = Metar::from_raw_text("EGLL 051750Z 24008KT 9999 FEW030 18/09 Q1021 NOSIG");
+    let without = Metar::from_raw_text("EGLL 051750Z 24008KT 9999 FEW030 18/09 Q1021");
+    let in_remarks = Metar::from_raw_text("KSJC 051753Z 18010KT 10SM FEW250 15/08 A3005 RMK NOSIG");
+
+    assert!(nosig.no_significant_change());
+    assert!(!without.no_significant_change());
+    assert!(!in_remarks.no_significant_change());
+}
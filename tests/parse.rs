@@ -0,0 +1,342 @@
+use std::fs;
+
+use futures::StreamExt;
+use metars::{
+    ColorState, Metar, MetarError, Metars, ParseStats, ReportType, Visibility, VisibilityQualifier,
+};
+use polars::frame::DataFrame;
+
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/metars.cache.csv"
+);
+
+fn fixture() -> String {
+    fs::read_to_string(FIXTURE).unwrap()
+}
+
+// `read_metar_file` deletes what it reads, so each call gets its own copy.
+fn try_read_fixture(contents: &str) -> Result<DataFrame, MetarError> {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("metars.csv");
+
+    fs::write(&path, contents).unwrap();
+
+    Metar::read_metar_file(path.to_str().unwrap())
+}
+
+fn read_fixture(contents: &str) -> DataFrame {
+    try_read_fixture(contents).unwrap()
+}
+
+fn parse_fixture() -> Metars {
+    Metar::parse_metars(&read_fixture(&fixture()))
+}
+
+fn station<'a>(metars: &'a Metars, station_id: &str) -> &'a Metar {
+    metars.iter().find(|x| x.station_id == station_id).unwrap()
+}
+
+#[test]
+fn keeps_only_k_stations() {
+    let metars = parse_fixture();
+
+    let stations: Vec<&str> = metars.iter().map(|x| x.station_id.as_str()).collect();
+
+    assert_eq!(stations, ["KSJC", "KSFO", "KDEN", "KACV", "KBOS"]);
+}
+
+#[test]
+fn parses_known_station() {
+    let metars = parse_fixture();
+    let metar = station(&metars, "KSJC");
+
+    assert_eq!(
+        metar.observation_time.map(|x| x.to_rfc3339()),
+        Some(String::from("2024-11-05T17:53:00+00:00"))
+    );
+    assert_eq!(metar.lat, Some(37.3591));
+    assert_eq!(metar.lon, Some(-121.924));
+    assert_eq!(metar.temp_celsius(), Some(15.0));
+    assert_eq!(metar.dewpoint_c.to_celsius(), Some(8.3));
+    assert_eq!(metar.wind_knots(), Some(10.0));
+    assert_eq!(metar.wind_dir_cardinal.as_deref(), Some("S"));
+    assert_eq!(
+        metar.visibility_statute_mi,
+        Visibility::StatuteMiles(Some(10.0), Some(VisibilityQualifier::GreaterThan))
+    );
+    assert_eq!(metar.altim_in_hg, Some(30.05));
+    assert_eq!(metar.clouds.len(), 1);
+    assert_eq!(metar.clouds[0].sky_cover.as_deref(), Some("FEW"));
+    assert_eq!(metar.clouds[0].cloud_base_ft_agl, Some(25000));
+    assert_eq!(metar.flight_category.as_deref(), Some("VFR"));
+    assert_eq!(metar.report_type, Some(ReportType::Metar));
+    assert_eq!(metar.elevation_ft.to_feet(), Some(180.0));
+    assert_eq!(metar.remarks.as_deref(), Some("AO2 SLP176 T01500083"));
+}
+
+#[test]
+fn parses_negative_temperatures_from_the_t_group() {
+    let metars = parse_fixture();
+    let metar = station(&metars, "KDEN");
+
+    assert_eq!(metar.temp_celsius(), Some(-5.0));
+    assert_eq!(metar.dewpoint_c.to_celsius(), Some(-7.2));
+    assert_eq!(metar.visibility_statute_mi.to_statute_miles(), Some(1.5));
+    assert_eq!(metar.wx_string.as_deref(), Some("-SN BR"));
+}
+
+#[test]
+fn parses_gusts_and_ceiling() {
+    let metars = parse_fixture();
+    let metar = station(&metars, "KSFO");
+
+    assert_eq!(metar.wind_gust_knots(), Some(25.0));
+    assert_eq!(metar.ceiling_ft(), Some(1200));
+}
+
+#[test]
+fn vertical_visibility_sets_the_ceiling() {
+    let metars = parse_fixture();
+    let metar = station(&metars, "KACV");
+
+    assert_eq!(metar.ceiling_ft(), Some(200));
+    assert!(metar
+        .clouds
+        .iter()
+        .all(|x| x.sky_cover.as_deref() != Some("OVX")));
+}
+
+#[test]
+fn parses_speci_report_type() {
+    let metars = parse_fixture();
+    let metar = station(&metars, "KBOS");
+
+    assert!(metar
+        .report_type
+        .as_ref()
+        .is_some_and(ReportType::is_special));
+}
+
+#[test]
+fn removes_the_csv_after_reading() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("metars.csv");
+
+    fs::copy(FIXTURE, &path).unwrap();
+    Metar::read_metar_file(path.to_str().unwrap()).unwrap();
+
+    assert!(!path.exists());
+}
+
+#[test]
+fn rejects_a_file_without_a_header_row() {
+    let result = try_read_fixture("No errors\nNo warnings\n");
+
+    assert!(matches!(result, Err(MetarError::Schema(_))));
+}
+
+#[test]
+fn calm_wind_is_distinct_from_missing_wind() {
+    let metars = parse_fixture();
+    let metar = station(&metars, "KACV");
+
+    assert!(metar.is_calm());
+    assert_eq!(metar.wind_summary(false).as_deref(), Some("Calm"));
+    assert!(metar.summary().starts_with("Winds calm"));
+
+    assert!(!Metar::default().is_calm());
+}
+
+#[test]
+fn drops_out_of_range_coordinates() {
+    let dataframe = read_fixture(&fixture().replace("37.3591,-121.924", "999.0,-121.924"));
+    let metars = Metar::parse_metars(&dataframe);
+    let metar = station(&metars, "KSJC");
+
+    assert_eq!(metar.lat, None);
+    assert_eq!(metar.lon, Some(-121.924));
+}
+
+#[test]
+fn keeps_visibility_qualifiers_from_the_csv() {
+    let dataframe = read_fixture(&fixture().replace(",0,0,,0.25,", ",0,0,,M1/4,"));
+    let metars = Metar::parse_metars(&dataframe);
+
+    assert_eq!(
+        station(&metars, "KSJC").visibility_statute_mi,
+        Visibility::StatuteMiles(Some(10.0), Some(VisibilityQualifier::GreaterThan))
+    );
+    assert_eq!(
+        station(&metars, "KACV").visibility_statute_mi,
+        Visibility::StatuteMiles(Some(0.25), Some(VisibilityQualifier::LessThan))
+    );
+}
+
+#[test]
+fn keeps_visibility_qualifiers_from_raw_text() {
+    let metar = Metar::from_raw_text("KACV 051756Z 00000KT M1/4SM FG VV002 10/10 A3010");
+
+    assert_eq!(
+        metar.visibility_statute_mi,
+        Visibility::StatuteMiles(Some(0.25), Some(VisibilityQualifier::LessThan))
+    );
+    assert_eq!(metar.computed_flight_category().as_deref(), Some("LIFR"));
+}
+
+#[test]
+fn rates_metric_visibility_from_the_csv() {
+    // The feed pairs a metric group with its value converted to statute miles.
+    let color_state = |group: &str, statute_mi: &str| {
+        let contents = fixture()
+            .replace("10SM FEW250", &format!("{group} FEW250"))
+            .replace(",10,,10+,", &format!(",10,,{statute_mi},"));
+
+        let metars = Metar::parse_metars(&read_fixture(&contents));
+
+        station(&metars, "KSJC").color_state()
+    };
+
+    assert_eq!(color_state("8000", "4.97"), Some(ColorState::Blu));
+    assert_eq!(color_state("1600", "0.99"), Some(ColorState::Ylo));
+}
+
+#[tokio::test]
+async fn stream_matches_the_collected_form() {
+    let dataframe = read_fixture(&fixture());
+
+    let streamed: Vec<Metar> = Metar::parse_metars_stream(&dataframe).await.collect().await;
+
+    assert_eq!(streamed, Metar::parse_metars(&dataframe).reports);
+}
+
+#[test]
+fn rejects_a_duplicated_header_column() {
+    let result = try_read_fixture(&fixture().replace(",temp_c,dewpoint_c,", ",temp_c,temp_c,"));
+
+    assert!(matches!(result, Err(MetarError::Schema(msg)) if msg.contains("temp_c")));
+}
+
+#[test]
+fn finds_the_header_after_a_longer_preamble() {
+    let fixture = fixture();
+
+    let preambles = [
+        fixture.replace("6 results\n", "6 results\n\n"),
+        fixture.replace(
+            "No warnings\n",
+            "1 warnings\nStation KXXX reported without an elevation\n",
+        ),
+    ];
+
+    for contents in preambles {
+        let metars = Metar::parse_metars(&read_fixture(&contents));
+
+        assert_eq!(metars.len(), 5);
+        assert_eq!(metars.reports[0].station_id, "KSJC");
+    }
+}
+
+#[test]
+fn counts_kept_and_skipped_rows() {
+    let (metars, stats) = Metar::parse_metars_with_stats(&read_fixture(&fixture()));
+
+    assert_eq!(metars.len(), 5);
+    assert_eq!(
+        stats,
+        ParseStats {
+            total_rows: 6,
+            kept: 5,
+            skipped_prefix: 1,
+        }
+    );
+}
+
+#[test]
+fn try_parse_reports_a_missing_column() {
+    let dataframe = polars::df!("station_id" => ["KSJC"]).unwrap();
+
+    let result = Metar::try_parse_metars(&dataframe);
+
+    assert!(matches!(result, Err(MetarError::Schema(msg)) if msg.contains("raw_text")));
+}
+
+#[test]
+#[should_panic(expected = "raw_text")]
+fn parse_panics_on_a_missing_column() {
+    let dataframe = polars::df!("station_id" => ["KSJC"]).unwrap();
+
+    Metar::parse_metars(&dataframe);
+}
+
+#[test]
+fn looks_up_columns_by_name() {
+    // Moving the last column up behind `raw_text` shifts every other column over by one.
+    let shuffled: Vec<String> = fixture()
+        .lines()
+        .map(|line| {
+            let mut fields: Vec<&str> = line.split(',').collect();
+
+            if let Some(last) = fields.pop().filter(|_| !fields.is_empty()) {
+                fields.insert(1, last);
+            }
+
+            fields.join(",")
+        })
+        .collect();
+
+    let metars = Metar::parse_metars(&read_fixture(&shuffled.join("\n")));
+
+    assert_eq!(metars.len(), 5);
+
+    let metar = station(&metars, "KSJC");
+
+    assert!(metar.raw_text.starts_with("KSJC 051753Z"));
+    assert_eq!(metar.temp_c.to_celsius(), Some(15.0));
+    assert_eq!(metar.dewpoint_c.to_celsius(), Some(8.3));
+    assert_eq!(metar.lat, Some(37.3591));
+    assert_eq!(metar.elevation_m.to_meters(), Some(55.0));
+}
+
+#[test]
+fn keeps_a_short_row_with_missing_fields() {
+    let contents = fixture() + "KXYZ 051753Z AUTO 00000KT,KXYZ\n";
+    let (metars, stats) = Metar::parse_metars_with_stats(&read_fixture(&contents));
+
+    assert_eq!(
+        stats,
+        ParseStats {
+            total_rows: 7,
+            kept: 6,
+            skipped_prefix: 1,
+        }
+    );
+
+    let metar = station(&metars, "KXYZ");
+
+    assert_eq!(metar.temp_c.to_celsius(), None);
+    assert_eq!(metar.lat, None);
+}
+
+#[test]
+fn reads_the_maintenance_indicator_column() {
+    let flagged: Vec<String> = fixture()
+        .lines()
+        .map(|line| {
+            let mut fields: Vec<&str> = line.split(',').collect();
+
+            if fields.get(1) == Some(&"KSFO") {
+                fields[16] = "TRUE";
+            }
+
+            fields.join(",")
+        })
+        .collect();
+
+    let metars = Metar::parse_metars(&read_fixture(&flagged.join("\n")));
+
+    assert_eq!(station(&metars, "KSFO").maintenance_indicator, Some(true));
+    assert!(station(&metars, "KSFO").needs_maintenance());
+    assert_eq!(station(&metars, "KSJC").maintenance_indicator, None);
+    assert!(!station(&metars, "KSJC").needs_maintenance());
+}